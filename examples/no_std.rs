@@ -0,0 +1,44 @@
+// проверка, что таблицей можно пользоваться без `std`: хэшер передается явно,
+// а сама библиотека собирается только с `core` и `alloc`
+//
+//     cargo run --example no_std --no-default-features
+
+use core::hash::{BuildHasherDefault, Hasher};
+
+use hashmap::HashMap;
+
+struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> FnvHasher {
+        FnvHasher(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+type FnvState = BuildHasherDefault<FnvHasher>;
+
+fn main() {
+    let mut map: HashMap<u32, u32, FnvState> = HashMap::with_hasher(FnvState::default());
+
+    for key in 0..1000 {
+        map.insert(key, key * key);
+    }
+
+    map.retain(|key, _| key % 3 == 0);
+
+    assert_eq!(map.len(), 334);
+    assert_eq!(map.get(&999), Some(&998001));
+    assert_eq!(map.get(&998), None);
+}
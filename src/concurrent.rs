@@ -0,0 +1,197 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::sync::RwLock;
+
+use crate::{HashMap, RawEntryMut};
+
+const DEFAULT_SHARDS: usize = 16;
+
+/// таблица для нескольких потоков: ключи распределяются по `shards` независимым
+/// `HashMap` под своими `RwLock`, и каждая операция блокирует только один из них.
+/// шард выбирается по младшим битам хэша ключа, а сам хэш передается в шард через
+/// `raw_entry`, поэтому ключ хэшируется один раз
+pub struct ShardedHashMap<K, V, S = RandomState> {
+    shards: Box<[RwLock<HashMap<K, V, S>>]>,
+    hash_builder: S,
+}
+
+impl<K, V> ShardedHashMap<K, V, RandomState> {
+    pub fn new() -> ShardedHashMap<K, V, RandomState> {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+
+    pub fn with_shards(shards: usize) -> ShardedHashMap<K, V, RandomState> {
+        Self::with_shards_and_hasher(shards, RandomState::new())
+    }
+}
+
+impl<K, V> Default for ShardedHashMap<K, V, RandomState> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, S: Clone> ShardedHashMap<K, V, S> {
+    /// количество шардов должно быть степенью двойки. клоны `hash_builder` должны
+    /// давать одинаковые хэши, как у `RandomState` и `BuildHasherDefault`
+    pub fn with_shards_and_hasher(shards: usize, hash_builder: S) -> ShardedHashMap<K, V, S> {
+        assert!(
+            shards.is_power_of_two(),
+            "the number of shards must be a power of two"
+        );
+
+        ShardedHashMap {
+            shards: (0..shards)
+                .map(|_| RwLock::new(HashMap::with_hasher(hash_builder.clone())))
+                .collect(),
+            hash_builder,
+        }
+    }
+}
+
+impl<K, V, S> ShardedHashMap<K, V, S> {
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// сумма длин шардов. шарды блокируются по очереди, поэтому при параллельных
+    /// вставках результат может не совпадать ни с одним моментом времени
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap().len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> ShardedHashMap<K, V, S> {
+    fn shard<Q: ?Sized + Hash>(&self, key: &Q) -> (u64, &RwLock<HashMap<K, V, S>>) {
+        let hash = self.hash_builder.hash_one(key);
+        let shard = &self.shards[hash as usize & (self.shards.len() - 1)];
+        (hash, shard)
+    }
+
+    /// копия значения, ссылку нельзя вернуть без удержания блокировки
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+        V: Clone,
+    {
+        let (hash, shard) = self.shard(key);
+        let map = shard.read().unwrap();
+
+        map.raw_entry()
+            .from_key_hashed_nocheck(hash, key)
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let (hash, shard) = self.shard(key);
+        let map = shard.read().unwrap();
+
+        map.raw_entry().from_key_hashed_nocheck(hash, key).is_some()
+    }
+
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let (hash, shard) = self.shard(&key);
+        let mut map = shard.write().unwrap();
+
+        match map.raw_entry_mut().from_key_hashed_nocheck(hash, &key) {
+            RawEntryMut::Occupied(mut entry) => Some(entry.insert(value)),
+            RawEntryMut::Vacant(entry) => {
+                entry.insert(key, value);
+                None
+            }
+        }
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let (hash, shard) = self.shard(key);
+        let mut map = shard.write().unwrap();
+
+        match map.raw_entry_mut().from_key_hashed_nocheck(hash, key) {
+            RawEntryMut::Occupied(entry) => Some(entry.remove_entry().1),
+            RawEntryMut::Vacant(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::ShardedHashMap;
+
+    #[test]
+    fn disjoint_threads() {
+        let map: ShardedHashMap<usize, usize> = ShardedHashMap::new();
+
+        thread::scope(|scope| {
+            for thread in 0..8 {
+                let map = &map;
+
+                scope.spawn(move || {
+                    for key in thread * 10_000..(thread + 1) * 10_000 {
+                        assert_eq!(map.insert(key, key * 2), None);
+                    }
+
+                    for key in (thread * 10_000..(thread + 1) * 10_000).step_by(2) {
+                        assert_eq!(map.remove(&key), Some(key * 2));
+                    }
+                });
+            }
+        });
+
+        assert_eq!(map.len(), 40_000);
+
+        for key in 0..80_000 {
+            assert_eq!(map.get(&key), (key % 2 == 1).then_some(key * 2));
+        }
+
+        assert_eq!(map.insert(1, 0), Some(2));
+        assert!(map.contains_key(&1));
+        assert!(!map.contains_key(&80_000));
+    }
+
+    #[test]
+    fn remove_keeps_full_shard() {
+        let map: ShardedHashMap<usize, usize> = ShardedHashMap::with_shards(1);
+        let buckets = || map.shards[0].read().unwrap().buckets();
+        let mut key = 0;
+
+        map.insert(key, key);
+
+        while map.len() < map.shards[0].read().unwrap().capacity() {
+            key += 1;
+            map.insert(key, key);
+        }
+
+        let full = buckets();
+
+        assert_eq!(map.remove(&(key + 1)), None);
+        assert_eq!(buckets(), full);
+
+        assert_eq!(map.remove(&key), Some(key));
+        assert_eq!(buckets(), full);
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn shards_power_of_two() {
+        ShardedHashMap::<usize, usize>::with_shards(3);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{is_full, DefaultHashBuilder, HashMap, Slot};
+
+/// позиция в таблице для однократного прохода по слотам. в отличие от `iter_mut` и
+/// последующих `remove` позволяет удалить текущий элемент сразу, без повторного
+/// пробирования. курсор держит таблицу заимствованной, поэтому переразместить ее в
+/// обход курсора нельзя, а удаление через него не сдвигает остальные элементы
+pub struct Cursor<'a, K, V, S = DefaultHashBuilder> {
+    pub(crate) map: &'a mut HashMap<K, V, S>,
+    // слот, с которого продолжится обход
+    pub(crate) index: usize,
+    // занятый слот, на котором стоит курсор
+    pub(crate) current: Option<usize>,
+}
+
+impl<K, V, S> Cursor<'_, K, V, S> {
+    /// переходит к следующему занятому слоту
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<(&K, &mut V)> {
+        self.current = None;
+
+        while self.index < self.map.capacity {
+            let idx = self.index;
+            self.index += 1;
+
+            if is_full(unsafe { self.map.flag(idx) }) {
+                self.current = Some(idx);
+                return self.current();
+            }
+        }
+
+        None
+    }
+
+    /// элемент под курсором, `None` до первого `next`, после удаления и в конце обхода
+    pub fn current(&mut self) -> Option<(&K, &mut V)> {
+        let idx = self.current?;
+        debug_assert!(
+            is_full(unsafe { self.map.flag(idx) }),
+            "cursor points to a slot that is no longer occupied"
+        );
+
+        let slot = unsafe { self.map.slot_mut(idx) };
+        Some((&slot.key, &mut slot.value))
+    }
+
+    /// удаляет элемент под курсором, следующий `next` продолжит со следующего слота
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        let idx = self.current.take()?;
+        let Slot { key, value } = unsafe { self.map.take(idx) };
+        Some((key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::HashMap;
+
+    #[test]
+    fn remove_every_third() {
+        let mut hashmap: HashMap<usize, usize> = (0..1000).map(|key| (key, key)).collect();
+        let mut removed = Vec::new();
+        let mut cursor = hashmap.cursor();
+        let mut seen = 0;
+
+        assert_eq!(cursor.current(), None);
+
+        while let Some((_, value)) = cursor.next() {
+            *value += 1;
+            seen += 1;
+
+            if seen % 3 == 0 {
+                let (key, value) = cursor.remove_current().unwrap();
+                assert_eq!(value, key + 1);
+                assert_eq!(cursor.current(), None);
+                assert_eq!(cursor.remove_current(), None);
+                removed.push(key);
+            }
+        }
+
+        assert_eq!(seen, 1000);
+        assert_eq!(removed.len(), 333);
+        assert_eq!(hashmap.len(), 667);
+
+        for key in removed {
+            assert_eq!(hashmap.get(&key), None);
+        }
+
+        for (&key, &value) in &hashmap {
+            assert_eq!(value, key + 1);
+        }
+    }
+}
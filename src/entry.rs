@@ -0,0 +1,377 @@
+use core::fmt::{self, Debug};
+use core::hash::{BuildHasher, Hash};
+
+use crate::{DefaultHashBuilder, HashMap};
+
+/// слот таблицы для одного ключа, найденный за один проход пробирования
+pub enum Entry<'a, K, V, S = DefaultHashBuilder> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+/// слот, в котором уже лежит значение для ключа
+pub struct OccupiedEntry<'a, K, V, S = DefaultHashBuilder> {
+    pub(crate) map: &'a mut HashMap<K, V, S>,
+    pub(crate) index: usize,
+}
+
+/// свободный слот, в который будет вставлен ключ
+pub struct VacantEntry<'a, K, V, S = DefaultHashBuilder> {
+    pub(crate) map: &'a mut HashMap<K, V, S>,
+    pub(crate) hash: usize,
+    pub(crate) key: K,
+    pub(crate) index: usize,
+}
+
+/// слоты для нескольких ключей по очереди, место под все ключи выделяется один раз
+/// при создании по нижней оценке их количества
+pub struct Entries<'a, K, V, I, S = DefaultHashBuilder> {
+    pub(crate) map: &'a mut HashMap<K, V, S>,
+    pub(crate) keys: I,
+}
+
+impl<K: Hash + Eq, V, I: Iterator<Item = K>, S: BuildHasher> Entries<'_, K, V, I, S> {
+    /// слот для следующего ключа. каждый слот нужно отпустить до взятия следующего
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<Entry<'_, K, V, S>> {
+        let key = self.keys.next()?;
+        Some(self.map.entry(key))
+    }
+}
+
+/// ошибка `HashMap::try_insert`: ключ уже есть в таблице
+pub struct OccupiedError<'a, K, V, S = DefaultHashBuilder> {
+    /// слот с уже лежащим значением
+    pub entry: OccupiedEntry<'a, K, V, S>,
+    /// значение, которое не было вставлено
+    pub value: V,
+}
+
+impl<K: Debug, V: Debug, S> Debug for OccupiedError<'_, K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedError")
+            .field("key", self.entry.key())
+            .field("old_value", self.entry.get())
+            .field("new_value", &self.value)
+            .finish()
+    }
+}
+
+impl<K: Debug, V: Debug, S> fmt::Display for OccupiedError<'_, K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to insert {:?}, key {:?} already exists with value {:?}",
+            self.value,
+            self.entry.key(),
+            self.entry.get(),
+        )
+    }
+}
+
+impl<'a, K, V, S> Entry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> Entry<'a, K, V, S> {
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// как `or_insert_with`, но значение строится по ключу
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<'a, K, V, S> OccupiedEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        unsafe { &self.map.slot(self.index).key }
+    }
+
+    pub fn get(&self) -> &V {
+        unsafe { &self.map.slot(self.index).value }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { &mut self.map.slot_mut(self.index).value }
+    }
+
+    /// в отличие от `get_mut` ссылка живет столько же, сколько и заимствование таблицы
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { &mut self.map.slot_mut(self.index).value }
+    }
+
+    /// заменяет значение, возвращая старое
+    pub fn insert(&mut self, value: V) -> V {
+        core::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        let slot = unsafe { self.map.take(self.index) };
+        (slot.key, slot.value)
+    }
+}
+
+impl<'a, K, V, S> VacantEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// записывает значение в найденный при поиске слот. если для вставки таблице нужно
+    /// вырасти или перехэшироваться, найденный слот устаревает, и после роста ищется новый
+    pub fn insert(self, value: V) -> &'a mut V
+    where
+        K: Hash + Eq,
+        S: BuildHasher,
+    {
+        let map = self.map;
+        let mut index = self.index;
+
+        if map.items + 1 + map.tombstones > map.capacity() {
+            map.reserve(1);
+            index = map.find_insert_slot(self.hash);
+        }
+
+        unsafe { map.insert_in_slot(index, self.hash, self.key, value) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Entry, HashMap};
+
+    #[test]
+    fn try_insert() {
+        let mut hashmap: HashMap<usize, String> = HashMap::new();
+
+        *hashmap.try_insert(1, "one".to_string()).unwrap() += "!";
+        assert_eq!(hashmap[&1], "one!");
+
+        let error = hashmap.try_insert(1, "uno".to_string()).unwrap_err();
+        assert_eq!(error.entry.key(), &1);
+        assert_eq!(error.entry.get(), "one!");
+        assert_eq!(error.value, "uno");
+
+        assert_eq!(hashmap.len(), 1);
+        assert_eq!(hashmap[&1], "one!");
+    }
+
+    #[test]
+    fn word_count() {
+        let text = "a b c a b a d";
+        let mut hashmap: HashMap<&str, usize> = HashMap::new();
+
+        for word in text.split(' ') {
+            *hashmap.entry(word).or_insert(0) += 1;
+        }
+
+        assert_eq!(hashmap.len(), 4);
+        assert_eq!(hashmap.get("a").copied(), Some(3));
+        assert_eq!(hashmap.get("b").copied(), Some(2));
+        assert_eq!(hashmap.get("c").copied(), Some(1));
+        assert_eq!(hashmap.get("d").copied(), Some(1));
+    }
+
+    #[test]
+    fn or_insert_with() {
+        let mut hashmap: HashMap<usize, String> = HashMap::new();
+        hashmap.insert(1, "one".to_string());
+
+        let value = hashmap.entry(1).or_insert_with(|| unreachable!());
+        assert_eq!(value, "one");
+
+        hashmap.entry(2).or_insert_with(|| "two".to_string());
+        assert_eq!(hashmap.get(&2).map(String::as_str), Some("two"));
+        assert_eq!(hashmap.len(), 2);
+    }
+
+    #[test]
+    fn or_default() {
+        let mut hashmap: HashMap<usize, Vec<usize>> = HashMap::new();
+
+        for number in 0..10 {
+            hashmap.entry(number % 3).or_default().push(number);
+        }
+
+        assert_eq!(hashmap.len(), 3);
+        assert_eq!(hashmap[&0], [0, 3, 6, 9]);
+        assert_eq!(hashmap[&1], [1, 4, 7]);
+        assert_eq!(hashmap[&2], [2, 5, 8]);
+    }
+
+    #[test]
+    fn occupied() {
+        let mut hashmap: HashMap<String, usize> = HashMap::new();
+        hashmap.insert("one".to_string(), 1);
+
+        match hashmap.entry("one".to_string()) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), "one");
+                assert_eq!(*entry.get(), 1);
+
+                *entry.get_mut() += 10;
+                assert_eq!(entry.insert(20), 11);
+
+                *entry.into_mut() += 1;
+            }
+            Entry::Vacant(_) => unreachable!(),
+        }
+
+        assert_eq!(hashmap["one"], 21);
+
+        match hashmap.entry("one".to_string()) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), 21),
+            Entry::Vacant(_) => unreachable!(),
+        }
+
+        assert!(hashmap.is_empty());
+        assert_eq!(hashmap.get("one"), None);
+    }
+
+    #[test]
+    fn vacant() {
+        let mut hashmap: HashMap<String, usize> = HashMap::new();
+
+        match hashmap.entry("two".to_string()) {
+            Entry::Vacant(entry) => {
+                assert_eq!(entry.key(), "two");
+                *entry.insert(2) *= 10;
+            }
+            Entry::Occupied(_) => unreachable!(),
+        }
+
+        assert_eq!(hashmap["two"], 20);
+        assert_eq!(hashmap.entry("three".to_string()).key(), "three");
+        assert_eq!(hashmap.len(), 1);
+    }
+
+    #[test]
+    fn or_insert_with_key() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+
+        for key in 0..10 {
+            assert_eq!(
+                *hashmap.entry(key).or_insert_with_key(|key| key * 2),
+                key * 2
+            );
+        }
+
+        let value = hashmap.entry(3).or_insert_with_key(|_| unreachable!());
+        assert_eq!(*value, 6);
+        assert_eq!(hashmap.len(), 10);
+    }
+
+    #[test]
+    fn entries() {
+        let mut hashmap: HashMap<&str, usize> = HashMap::new();
+        hashmap.insert("b", 10);
+
+        let keys = ["a", "b", "c", "a", "d", "b", "a"];
+        let mut entries = hashmap.entries(keys);
+        let buckets = entries.map.buckets();
+
+        while let Some(entry) = entries.next() {
+            *entry.or_insert(0) += 1;
+        }
+
+        // все вставки поместились в выделенное заранее место
+        assert_eq!(hashmap.buckets(), buckets);
+        assert_eq!(hashmap.len(), 4);
+        assert_eq!(hashmap["a"], 3);
+        assert_eq!(hashmap["b"], 12);
+        assert_eq!(hashmap["c"], 1);
+        assert_eq!(hashmap["d"], 1);
+    }
+
+    #[test]
+    fn and_modify() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+
+        for _ in 0..3 {
+            hashmap
+                .entry(7)
+                .and_modify(|value| *value *= 10)
+                .or_insert(1);
+        }
+
+        assert_eq!(hashmap.get(&7).copied(), Some(100));
+    }
+
+    #[test]
+    fn grow_on_insert() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::with_capacity(100);
+        let buckets = hashmap.buckets();
+        let capacity = hashmap.capacity();
+
+        for key in 0..capacity {
+            *hashmap.entry(key).or_insert(0) += key;
+        }
+
+        // таблица заполнена до предела, но поиск существующего ключа ее не растит
+        assert_eq!(hashmap.len(), capacity);
+        assert_eq!(hashmap.buckets(), buckets);
+        assert!(matches!(hashmap.entry(0), Entry::Occupied(_)));
+        assert_eq!(hashmap.buckets(), buckets);
+
+        // слот найден до роста, вставка находит новый
+        match hashmap.entry(capacity) {
+            Entry::Vacant(entry) => *entry.insert(0) += capacity,
+            Entry::Occupied(_) => unreachable!(),
+        }
+
+        assert!(hashmap.buckets() > buckets);
+        assert_eq!(hashmap.len(), capacity + 1);
+
+        for key in 0..=capacity {
+            assert_eq!(hashmap.get(&key), Some(&key));
+        }
+
+        let mut empty: HashMap<usize, usize> = HashMap::new();
+        assert_eq!(*empty.entry(1).or_insert(1), 1);
+        assert_eq!(empty.get(&1), Some(&1));
+    }
+}
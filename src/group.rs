@@ -0,0 +1,152 @@
+// группа из `GROUP_WIDTH` подряд идущих флагов, которые сравниваются за одну операцию.
+// на x86 с SSE2 используются векторные инструкции, иначе флаги перебираются по одному
+
+pub(crate) const GROUP_WIDTH: usize = 16;
+
+/// битовая маска подходящих флагов группы, младший бит соответствует первому флагу
+#[derive(Clone, Copy)]
+pub(crate) struct BitMask(u16);
+
+impl BitMask {
+    pub(crate) fn any_bit_set(self) -> bool {
+        self.0 != 0
+    }
+
+    pub(crate) fn lowest_set_bit(self) -> Option<usize> {
+        if self.0 == 0 {
+            None
+        } else {
+            Some(self.0.trailing_zeros() as usize)
+        }
+    }
+
+    // сколько флагов подряд с начала группы не попало в маску
+    pub(crate) fn trailing_zeros(self) -> usize {
+        self.0.trailing_zeros() as usize
+    }
+
+    // сколько флагов подряд с конца группы не попало в маску
+    pub(crate) fn leading_zeros(self) -> usize {
+        self.0.leading_zeros() as usize
+    }
+}
+
+impl Iterator for BitMask {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let bit = self.lowest_set_bit()?;
+        self.0 &= self.0 - 1;
+        Some(bit)
+    }
+}
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2"
+))]
+mod imp {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    use super::BitMask;
+
+    #[derive(Clone, Copy)]
+    pub(crate) struct Group(__m128i);
+
+    impl Group {
+        /// # Safety
+        ///
+        /// `ctrl` должен указывать на `GROUP_WIDTH` доступных для чтения байт
+        pub(crate) unsafe fn load(ctrl: *const u8) -> Group {
+            Group(_mm_loadu_si128(ctrl as *const __m128i))
+        }
+
+        pub(crate) fn match_byte(self, byte: u8) -> BitMask {
+            unsafe {
+                let cmp = _mm_cmpeq_epi8(self.0, _mm_set1_epi8(byte as i8));
+                BitMask(_mm_movemask_epi8(cmp) as u16)
+            }
+        }
+
+        // у занятых слотов старший бит флага выставлен, у пустых и удаленных нет
+        pub(crate) fn match_empty_or_deleted(self) -> BitMask {
+            unsafe { BitMask(!(_mm_movemask_epi8(self.0) as u16)) }
+        }
+    }
+}
+
+#[cfg(not(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2"
+)))]
+mod imp {
+    use super::{BitMask, GROUP_WIDTH};
+
+    #[derive(Clone, Copy)]
+    pub(crate) struct Group([u8; GROUP_WIDTH]);
+
+    impl Group {
+        /// # Safety
+        ///
+        /// `ctrl` должен указывать на `GROUP_WIDTH` доступных для чтения байт
+        pub(crate) unsafe fn load(ctrl: *const u8) -> Group {
+            Group(core::ptr::read_unaligned(ctrl as *const [u8; GROUP_WIDTH]))
+        }
+
+        fn match_by(self, f: impl Fn(u8) -> bool) -> BitMask {
+            let mut mask = 0;
+
+            for (idx, &flag) in self.0.iter().enumerate() {
+                if f(flag) {
+                    mask |= 1 << idx;
+                }
+            }
+
+            BitMask(mask)
+        }
+
+        pub(crate) fn match_byte(self, byte: u8) -> BitMask {
+            self.match_by(|flag| flag == byte)
+        }
+
+        pub(crate) fn match_empty_or_deleted(self) -> BitMask {
+            self.match_by(|flag| flag & 0x80 == 0)
+        }
+    }
+}
+
+pub(crate) use imp::Group;
+
+impl Group {
+    pub(crate) fn match_empty(self) -> BitMask {
+        self.match_byte(crate::EMPTY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Group, GROUP_WIDTH};
+    use crate::{DELETED, EMPTY};
+
+    #[test]
+    fn matches() {
+        let mut ctrl = [EMPTY; GROUP_WIDTH];
+        ctrl[1] = 0x85;
+        ctrl[4] = DELETED;
+        ctrl[9] = 0x85;
+        ctrl[15] = 0xff;
+
+        let group = unsafe { Group::load(ctrl.as_ptr()) };
+
+        assert_eq!(group.match_byte(0x85).collect::<Vec<_>>(), [1, 9]);
+        assert_eq!(group.match_byte(0x86).lowest_set_bit(), None);
+        assert_eq!(group.match_empty().count(), 12);
+        assert!(!group.match_empty().collect::<Vec<_>>().contains(&4));
+        assert_eq!(group.match_empty_or_deleted().count(), 13);
+        assert_eq!(group.match_empty_or_deleted().leading_zeros(), 1);
+        assert_eq!(group.match_byte(0xff).trailing_zeros(), 15);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{checked_buckets_for, max_load, DEFAULT_MAX_LOAD_FACTOR, MIN_GROW_CAPACITY};
+
+/// как растет таблица, заданная через `HashMap::with_growth_policy`. размеры измеряются в
+/// слотах, а `max_load` должна не убывать с ростом `capacity`. политикам не обязательно
+/// выдавать степени двойки, для остальных размеров таблица пробирует линейно
+pub trait GrowthPolicy: Sync {
+    /// сколько слотов выделить, когда в `current` слотах не помещается `needed` элементов.
+    /// если возвращенного размера не хватает, таблица возьмет минимальный подходящий
+    fn next_capacity(&self, current: usize, needed: usize) -> usize;
+
+    /// сколько элементов можно положить в `capacity` слотов. хотя бы один слот таблица
+    /// всегда оставляет пустым, даже если политика разрешает занять все
+    fn max_load(&self, capacity: usize) -> usize;
+}
+
+/// поведение таблицы без политики: рост как минимум вдвое до степени двойки
+/// и заполнение до 7/8 слотов
+pub struct DefaultGrowthPolicy;
+
+impl GrowthPolicy for DefaultGrowthPolicy {
+    fn next_capacity(&self, current: usize, needed: usize) -> usize {
+        let needed = needed
+            .max(self.max_load(current).saturating_mul(2))
+            .max(MIN_GROW_CAPACITY);
+        checked_buckets_for(needed, DEFAULT_MAX_LOAD_FACTOR).unwrap_or(usize::MAX)
+    }
+
+    fn max_load(&self, capacity: usize) -> usize {
+        max_load(capacity, DEFAULT_MAX_LOAD_FACTOR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DefaultGrowthPolicy, GrowthPolicy};
+    use crate::HashMap;
+
+    // рост в полтора раза с заполнением до 3/4 слотов
+    struct OneAndHalf;
+
+    impl GrowthPolicy for OneAndHalf {
+        fn next_capacity(&self, current: usize, needed: usize) -> usize {
+            (current + current / 2).max(needed * 4 / 3).max(16)
+        }
+
+        fn max_load(&self, capacity: usize) -> usize {
+            capacity * 3 / 4
+        }
+    }
+
+    // размеры таблицы по мере вставки `count` ключей
+    fn progression(mut hashmap: HashMap<usize, usize>, count: usize) -> Vec<usize> {
+        let mut sizes = Vec::new();
+
+        for key in 0..count {
+            hashmap.insert(key, key);
+
+            if sizes.last() != Some(&hashmap.buckets()) {
+                sizes.push(hashmap.buckets());
+            }
+        }
+
+        assert!((0..count).all(|key| hashmap[&key] == key));
+        sizes
+    }
+
+    #[test]
+    fn default_policy() {
+        static POLICY: DefaultGrowthPolicy = DefaultGrowthPolicy;
+
+        assert_eq!(
+            progression(HashMap::with_growth_policy(&POLICY), 10_000),
+            progression(HashMap::new(), 10_000),
+        );
+    }
+
+    #[test]
+    fn one_and_half() {
+        static POLICY: OneAndHalf = OneAndHalf;
+
+        let mut hashmap = HashMap::with_growth_policy(&POLICY);
+        let sizes = progression(HashMap::with_growth_policy(&POLICY), 10_000);
+
+        assert_eq!(&sizes[..5], [16, 24, 36, 54, 81]);
+        assert_ne!(sizes, progression(HashMap::new(), 10_000));
+
+        hashmap.reserve_exact(100);
+        assert_eq!(hashmap.buckets(), 134);
+        assert_eq!(hashmap.capacity(), 100);
+
+        hashmap.extend((0..10).map(|key| (key, key)));
+        hashmap.shrink_to_fit();
+        assert_eq!(hashmap.buckets(), 14);
+        assert_eq!(hashmap.capacity(), 10);
+        assert!((0..10).all(|key| hashmap[&key] == key));
+    }
+}
@@ -0,0 +1,103 @@
+use core::hash::{BuildHasherDefault, Hasher};
+
+const SEED: u64 = 0x517cc1b727220a95;
+
+/// быстрый хэшер для целочисленных ключей по образцу FxHash из rustc: слово ключа
+/// смешивается одним умножением, без побайтового обхода. не защищает от подобранных
+/// ключей, поэтому годится только для данных, которым можно доверять
+#[derive(Clone, Copy, Default)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+impl FxHasher {
+    #[inline]
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+
+        for chunk in &mut chunks {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            self.add(u64::from_le_bytes(word));
+        }
+
+        for &byte in chunks.remainder() {
+            self.add(byte as u64);
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, value: u8) {
+        self.add(value as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, value: u16) {
+        self.add(value as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, value: u32) {
+        self.add(value as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, value: u64) {
+        self.add(value);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, value: usize) {
+        self.add(value as u64);
+    }
+}
+
+/// строитель `FxHasher` для `HashMap::with_hasher`
+pub type FxBuildHasher = BuildHasherDefault<FxHasher>;
+
+#[cfg(test)]
+mod tests {
+    use super::FxBuildHasher;
+    use crate::HashMap;
+
+    fn max_probe_length(keys: impl Iterator<Item = usize>) -> usize {
+        let mut hashmap: HashMap<usize, usize, FxBuildHasher> =
+            HashMap::with_hasher(FxBuildHasher::default());
+        hashmap.extend(keys.map(|key| (key, key)));
+
+        hashmap
+            .keys()
+            .map(|key| hashmap.probe_length(key).unwrap())
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn sequential_keys() {
+        let sequential = max_probe_length(0..100_000);
+        let strided = max_probe_length((0..100_000).map(|key| key << 12));
+
+        // `FxHasher` детерминирован, а `RandomState` на тех же ключах дает от 70 до 100
+        // с треугольным пробированием и от 110 до 210 с линейным. при скоплении ключей
+        // цепочки шли бы на тысячи слотов
+        let bound = if cfg!(feature = "linear-probing") {
+            256
+        } else {
+            128
+        };
+
+        assert!(sequential <= bound, "{}", sequential);
+        assert!(strided <= bound, "{}", strided);
+    }
+}
@@ -0,0 +1,703 @@
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+use crate::{ctrl_len, deallocate, is_full, DefaultHashBuilder, HashMap, Slot, EMPTY};
+
+// обход занятых слотов таблицы по порядку, на нем построены все итераторы.
+// `items` - сколько занятых слотов еще не выдано, после последнего обход сразу прекращается
+pub(crate) struct RawIter<K, V> {
+    slots: NonNull<Slot<K, V>>,
+    ctrl: NonNull<u8>,
+    index: usize,
+    capacity: usize,
+    items: usize,
+}
+
+impl<K, V> RawIter<K, V> {
+    pub(crate) fn new(
+        slots: NonNull<Slot<K, V>>,
+        ctrl: NonNull<u8>,
+        capacity: usize,
+        items: usize,
+    ) -> RawIter<K, V> {
+        RawIter {
+            slots,
+            ctrl,
+            index: 0,
+            capacity,
+            items,
+        }
+    }
+}
+
+impl<K, V> RawIter<K, V> {
+    // делит оставшиеся слоты пополам, на этом построен параллельный обход
+    #[cfg(feature = "rayon")]
+    pub(crate) fn split(self) -> (RawIter<K, V>, Option<RawIter<K, V>>) {
+        let remaining = self.capacity - self.index;
+
+        if remaining < 2 {
+            return (self, None);
+        }
+
+        let middle = self.index + remaining / 2;
+        // занятые слоты левой половины приходится пересчитать
+        let left_items = (self.index..middle)
+            .filter(|&idx| is_full(unsafe { *self.ctrl.as_ptr().add(idx) }))
+            .count();
+        let right = RawIter {
+            index: middle,
+            items: self.items - left_items,
+            ..self
+        };
+
+        (
+            RawIter {
+                capacity: middle,
+                items: left_items,
+                ..self
+            },
+            Some(right),
+        )
+    }
+}
+
+impl<K, V> Clone for RawIter<K, V> {
+    fn clone(&self) -> Self {
+        RawIter { ..*self }
+    }
+}
+
+impl<K, V> Iterator for RawIter<K, V> {
+    type Item = *mut Slot<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.items == 0 {
+            return None;
+        }
+
+        while self.index < self.capacity {
+            let idx = self.index;
+            self.index += 1;
+
+            if is_full(unsafe { *self.ctrl.as_ptr().add(idx) }) {
+                self.items -= 1;
+                return Some(unsafe { self.slots.as_ptr().add(idx) });
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.items, Some(self.items))
+    }
+}
+
+impl<K, V> ExactSizeIterator for RawIter<K, V> {}
+
+/// итератор по парам ключ-значение, порядок совпадает с порядком слотов в таблице
+pub struct Iter<'a, K, V> {
+    pub(crate) inner: RawIter<K, V>,
+    pub(crate) marker: PhantomData<&'a (K, V)>,
+}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            inner: self.inner.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|slot| unsafe {
+            let slot = &*slot;
+            (&slot.key, &slot.value)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+// итератор ведет себя как `&HashMap`: только читает слоты по общей ссылке
+unsafe impl<K: Sync, V: Sync> Send for Iter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Iter<'_, K, V> {}
+
+/// итератор по парам ключ-значение с изменяемыми значениями
+pub struct IterMut<'a, K, V> {
+    pub(crate) inner: RawIter<K, V>,
+    pub(crate) marker: PhantomData<&'a mut (K, V)>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|slot| unsafe {
+            let slot = &mut *slot;
+            (&slot.key, &mut slot.value)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+// а этот как `&mut HashMap`: выдает изменяемые ссылки, каждую не больше одного раза
+unsafe impl<K: Send, V: Send> Send for IterMut<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for IterMut<'_, K, V> {}
+
+/// поглощающий итератор, забирает у таблицы память и отдает пары ключ-значение во владение
+pub struct IntoIter<K, V> {
+    pub(crate) inner: RawIter<K, V>,
+    pub(crate) slots: NonNull<Slot<K, V>>,
+    pub(crate) capacity: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|slot| unsafe {
+            let Slot { key, value, .. } = core::ptr::read(slot);
+            (key, value)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+impl<K, V> Drop for IntoIter<K, V> {
+    fn drop(&mut self) {
+        unsafe {
+            // невыданные элементы все еще принадлежат итератору
+            if core::mem::needs_drop::<Slot<K, V>>() {
+                for slot in &mut self.inner {
+                    slot.drop_in_place();
+                }
+            }
+
+            if self.capacity != 0 {
+                deallocate(self.slots, self.capacity);
+            }
+        }
+    }
+}
+
+unsafe impl<K: Send, V: Send> Send for IntoIter<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for IntoIter<K, V> {}
+
+/// поглощающий итератор по ключам таблицы
+pub struct IntoKeys<K, V> {
+    pub(crate) inner: IntoIter<K, V>,
+}
+
+impl<K, V> Iterator for IntoKeys<K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoKeys<K, V> {}
+impl<K, V> FusedIterator for IntoKeys<K, V> {}
+
+/// поглощающий итератор по значениям таблицы
+pub struct IntoValues<K, V> {
+    pub(crate) inner: IntoIter<K, V>,
+}
+
+impl<K, V> Iterator for IntoValues<K, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoValues<K, V> {}
+impl<K, V> FusedIterator for IntoValues<K, V> {}
+
+/// выдает все элементы таблицы во владение, оставляя ей выделенную память.
+/// на время работы итератора слоты забираются у таблицы, поэтому даже забытый
+/// итератор не приведет к повторному освобождению значений
+pub struct Drain<'a, K, V, S = DefaultHashBuilder> {
+    pub(crate) inner: RawIter<K, V>,
+    pub(crate) slots: NonNull<Slot<K, V>>,
+    pub(crate) ctrl: NonNull<u8>,
+    pub(crate) capacity: usize,
+    pub(crate) map: &'a mut HashMap<K, V, S>,
+}
+
+impl<K, V, S> Iterator for Drain<'_, K, V, S> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|slot| unsafe {
+            let Slot { key, value, .. } = core::ptr::read(slot);
+            (key, value)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, S> ExactSizeIterator for Drain<'_, K, V, S> {}
+impl<K, V, S> FusedIterator for Drain<'_, K, V, S> {}
+
+// элементы выдаются во владение, поэтому для передачи в другой поток нужен `Send`
+unsafe impl<K: Send, V: Send, S: Send> Send for Drain<'_, K, V, S> {}
+unsafe impl<K: Sync, V: Sync, S: Sync> Sync for Drain<'_, K, V, S> {}
+
+impl<K, V, S> Drop for Drain<'_, K, V, S> {
+    fn drop(&mut self) {
+        unsafe {
+            if core::mem::needs_drop::<Slot<K, V>>() {
+                for slot in &mut self.inner {
+                    slot.drop_in_place();
+                }
+            }
+
+            if self.capacity != 0 {
+                self.ctrl
+                    .as_ptr()
+                    .write_bytes(EMPTY, ctrl_len(self.capacity));
+            }
+        }
+
+        self.map.slots = self.slots;
+        self.map.ctrl = self.ctrl;
+        self.map.capacity = self.capacity;
+        self.map.items = 0;
+    }
+}
+
+/// удаляет и выдает элементы, для которых предикат вернул `true`, по мере обхода.
+/// если итератор бросить раньше, непросмотренные элементы останутся в таблице
+pub struct ExtractIf<'a, K, V, F, S = DefaultHashBuilder>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    pub(crate) map: &'a mut HashMap<K, V, S>,
+    pub(crate) index: usize,
+    pub(crate) pred: F,
+}
+
+impl<K, V, F, S> Iterator for ExtractIf<'_, K, V, F, S>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.map.capacity {
+            let idx = self.index;
+            self.index += 1;
+
+            unsafe {
+                if !is_full(self.map.flag(idx)) {
+                    continue;
+                }
+
+                let slot = self.map.slot_mut(idx);
+
+                // удаленный слот становится пустым или надгробием, а остальные элементы
+                // не сдвигаются, поэтому цепочки пробирования не нарушаются
+                if (self.pred)(&slot.key, &mut slot.value) {
+                    let Slot { key, value } = self.map.take(idx);
+                    return Some((key, value));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.map.items))
+    }
+}
+
+impl<K, V, F, S> FusedIterator for ExtractIf<'_, K, V, F, S> where F: FnMut(&K, &mut V) -> bool {}
+
+/// итератор по ключам таблицы
+pub struct Keys<'a, K, V> {
+    pub(crate) inner: Iter<'a, K, V>,
+}
+
+impl<K, V> Clone for Keys<'_, K, V> {
+    fn clone(&self) -> Self {
+        Keys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+/// итератор по значениям таблицы
+pub struct Values<'a, K, V> {
+    pub(crate) inner: Iter<'a, K, V>,
+}
+
+impl<K, V> Clone for Values<'_, K, V> {
+    fn clone(&self) -> Self {
+        Values {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+/// итератор по изменяемым значениям таблицы
+pub struct ValuesMut<'a, K, V> {
+    pub(crate) inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::HashMap;
+    use std::collections::HashSet;
+
+    #[test]
+    fn iter() {
+        let mut hashmap: HashMap<usize, f32> = HashMap::new();
+
+        for key in [3, 1, 40, 7, 12] {
+            hashmap.insert(key, key as f32 * 0.5);
+        }
+
+        hashmap.remove(&7);
+
+        let mut pairs: Vec<_> = hashmap.iter().map(|(&key, &value)| (key, value)).collect();
+        pairs.sort_by_key(|&(key, _)| key);
+
+        assert_eq!(pairs, [(1, 0.5), (3, 1.5), (12, 6.0), (40, 20.0)]);
+    }
+
+    #[test]
+    fn exact_size() {
+        let mut hashmap: HashMap<usize, usize> = (0..100).map(|key| (key, key)).collect();
+        hashmap.remove(&50);
+
+        let mut iter = hashmap.iter();
+        assert_eq!(iter.len(), hashmap.len());
+
+        for remaining in (0..hashmap.len()).rev() {
+            iter.next();
+            assert_eq!(iter.len(), remaining);
+        }
+
+        assert_eq!(iter.next(), None);
+        assert_eq!(hashmap.keys().len(), 99);
+        assert_eq!(hashmap.values().size_hint(), (99, Some(99)));
+        assert_eq!(hashmap.values_mut().len(), 99);
+
+        let mut drain = hashmap.drain();
+        drain.next();
+        assert_eq!(drain.len(), 98);
+        drop(drain);
+
+        let hashmap: HashMap<usize, usize> = (0..10).map(|key| (key, key)).collect();
+        assert_eq!(hashmap.clone().into_iter().len(), 10);
+        assert_eq!(hashmap.clone().into_keys().len(), 10);
+        assert_eq!(hashmap.into_values().len(), 10);
+    }
+
+    #[test]
+    fn fused() {
+        let mut hashmap: HashMap<usize, String> =
+            (0..3).map(|key| (key, key.to_string())).collect();
+
+        let mut iter = hashmap.iter();
+        assert_eq!(iter.by_ref().count(), 3);
+        assert!((0..5).all(|_| iter.next().is_none()));
+
+        let mut keys = hashmap.keys();
+        keys.by_ref().for_each(drop);
+        assert!((0..5).all(|_| keys.next().is_none()));
+
+        let mut values = hashmap.values();
+        values.by_ref().for_each(drop);
+        assert!((0..5).all(|_| values.next().is_none()));
+
+        let mut iter_mut = hashmap.iter_mut();
+        iter_mut.by_ref().for_each(drop);
+        assert!((0..5).all(|_| iter_mut.next().is_none()));
+
+        let mut drain = hashmap.drain();
+        assert_eq!(drain.by_ref().count(), 3);
+        assert!((0..5).all(|_| drain.next().is_none()));
+        drop(drain);
+
+        hashmap.insert(7, "seven".to_string());
+
+        let mut into_iter = hashmap.into_iter();
+        assert!(into_iter.next().is_some());
+        assert!((0..5).all(|_| into_iter.next().is_none()));
+    }
+
+    #[test]
+    fn extract_if() {
+        let mut hashmap: HashMap<usize, String> =
+            (0..100).map(|key| (key, key.to_string())).collect();
+
+        let mut odd: Vec<usize> = hashmap
+            .extract_if(|key, _| key % 2 == 1)
+            .map(|(key, value)| {
+                assert_eq!(value, key.to_string());
+                key
+            })
+            .collect();
+        odd.sort();
+
+        assert_eq!(odd, (1..100).step_by(2).collect::<Vec<_>>());
+        assert_eq!(hashmap.len(), 50);
+        assert!((0..100).all(|key| hashmap.contains_key(&key) == (key % 2 == 0)));
+
+        // брошенный итератор оставляет непросмотренные элементы на месте
+        assert!(hashmap.extract_if(|_, _| true).next().is_some());
+
+        assert_eq!(hashmap.len(), 49);
+        assert_eq!((0..100).filter(|key| hashmap.contains_key(key)).count(), 49);
+    }
+
+    #[test]
+    fn iter_empty() {
+        let hashmap: HashMap<usize, f32> = HashMap::new();
+        assert_eq!(hashmap.iter().next(), None);
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut hashmap: HashMap<usize, f32> = HashMap::new();
+
+        for key in 0..10 {
+            hashmap.insert(key, key as f32);
+        }
+
+        for (_, value) in hashmap.iter_mut() {
+            *value *= 2.0;
+        }
+
+        for key in 0..10 {
+            assert_eq!(hashmap.get(&key).copied(), Some(key as f32 * 2.0));
+        }
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut hashmap: HashMap<usize, String> = HashMap::new();
+
+        for key in 0..10 {
+            hashmap.insert(key, key.to_string());
+        }
+
+        let mut pairs: Vec<_> = hashmap.into_iter().collect();
+        pairs.sort();
+
+        let expected: Vec<_> = (0..10).map(|key| (key, key.to_string())).collect();
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn into_iter_partial() {
+        let mut hashmap: HashMap<String, String> = HashMap::new();
+
+        for key in 0..10 {
+            hashmap.insert(key.to_string(), key.to_string());
+        }
+
+        let mut iter = hashmap.into_iter();
+        let (key, value) = iter.next().unwrap();
+        assert_eq!(key, value);
+
+        // остальные строки освобождаются вместе с итератором
+        drop(iter);
+    }
+
+    #[test]
+    fn into_iter_refs() {
+        let mut hashmap: HashMap<usize, f32> = HashMap::new();
+        hashmap.insert(1, 1.0);
+        hashmap.insert(2, 2.0);
+
+        for (_, value) in &mut hashmap {
+            *value += 1.0;
+        }
+
+        let mut sum = 0.0;
+
+        for (_, value) in &hashmap {
+            sum += value;
+        }
+
+        assert_eq!(sum, 5.0);
+
+        // обходы по ссылкам идут в том же порядке, что `iter` и `iter_mut`
+        assert!((&hashmap).into_iter().eq(hashmap.iter()));
+        let order: Vec<usize> = (&mut hashmap).into_iter().map(|(&key, _)| key).collect();
+        assert!(order.iter().eq(hashmap.keys()));
+    }
+
+    #[test]
+    fn into_keys_values() {
+        let hashmap: HashMap<usize, String> = (0..10).map(|i| (i, i.to_string())).collect();
+
+        let mut values: Vec<String> = hashmap.clone().into_values().collect();
+        values.sort_by_key(|value| value.parse::<usize>().unwrap());
+        assert_eq!(values, (0..10).map(|i| i.to_string()).collect::<Vec<_>>());
+
+        let mut keys: Vec<usize> = hashmap.clone().into_keys().collect();
+        keys.sort();
+        assert_eq!(keys, (0..10).collect::<Vec<_>>());
+
+        // невыданные строки освобождаются вместе с итератором
+        let mut values = hashmap.into_values();
+        assert!(values.next().is_some());
+        drop(values);
+    }
+
+    #[test]
+    fn keys_values() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+        let inserted: HashSet<usize> = (0..50).map(|key| key * 3).collect();
+
+        for &key in &inserted {
+            hashmap.insert(key, key + 1);
+        }
+
+        let keys: HashSet<usize> = hashmap.keys().copied().collect();
+        assert_eq!(keys, inserted);
+
+        for value in hashmap.values_mut() {
+            *value -= 1;
+        }
+
+        let values: HashSet<usize> = hashmap.values().copied().collect();
+        assert_eq!(values, inserted);
+    }
+
+    #[test]
+    fn drain() {
+        let mut hashmap: HashMap<usize, String> = (0..20).map(|i| (i, i.to_string())).collect();
+        let capacity = hashmap.capacity();
+
+        let mut drain = hashmap.drain();
+
+        for _ in 0..10 {
+            let (key, value) = drain.next().unwrap();
+            assert_eq!(key.to_string(), value);
+        }
+
+        drop(drain);
+
+        assert!(hashmap.is_empty());
+        assert_eq!(hashmap.capacity(), capacity);
+        assert_eq!(hashmap.iter().next(), None);
+
+        for key in 0..20 {
+            assert_eq!(hashmap.get(&key), None);
+        }
+
+        hashmap.insert(1, "1".to_string());
+        assert_eq!(hashmap.drain().collect::<Vec<_>>(), [(1, "1".to_string())]);
+        assert!(hashmap.is_empty());
+    }
+
+    #[test]
+    fn send_sync() {
+        fn is_send<T: Send>(_: &T) {}
+        fn is_sync<T: Sync>(_: &T) {}
+
+        let mut hashmap: HashMap<usize, String> = HashMap::new();
+        let set: crate::HashSet<usize> = crate::HashSet::new();
+
+        is_send(&hashmap.iter());
+        is_sync(&hashmap.iter());
+        is_send(&hashmap.keys());
+        is_sync(&hashmap.values());
+        is_send(&set.iter());
+        is_sync(&set.iter());
+        is_send(&hashmap.iter_mut());
+        is_sync(&hashmap.iter_mut());
+        is_send(&hashmap.values_mut());
+        is_sync(&hashmap.values_mut());
+        is_send(&hashmap.drain());
+        is_sync(&hashmap.drain());
+        is_sync(&hashmap.clone().into_iter());
+    }
+}
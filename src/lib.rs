@@ -1,319 +1,3489 @@
-#![feature(alloc_layout_extra)]
-#![feature(test)]
+#![cfg_attr(test, feature(test))]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut, Index};
+use core::ptr::NonNull;
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+
+#[cfg(feature = "std")]
+pub mod concurrent;
+mod cursor;
+mod entry;
+mod group;
+mod growth;
+pub mod hash;
+mod iter;
+#[cfg(test)]
+mod probing_bench;
+mod raw_entry;
+mod raw_slots;
+#[cfg(feature = "rayon")]
+mod rayon_impl;
+mod robin_hood;
+#[cfg(feature = "serde")]
+mod serde_impl;
+pub mod set;
+
+#[cfg(feature = "std")]
+pub use concurrent::ShardedHashMap;
+pub use cursor::Cursor;
+pub use entry::{Entries, Entry, OccupiedEntry, OccupiedError, VacantEntry};
+use group::{Group, GROUP_WIDTH};
+pub use growth::{DefaultGrowthPolicy, GrowthPolicy};
+use iter::RawIter;
+pub use iter::{
+    Drain, ExtractIf, IntoIter, IntoKeys, IntoValues, Iter, IterMut, Keys, Values, ValuesMut,
+};
+pub use raw_entry::{
+    RawEntryBuilder, RawEntryBuilderMut, RawEntryMut, RawOccupiedEntryMut, RawVacantEntryMut,
+};
+pub use raw_slots::{RawSlot, RawSlots};
+#[cfg(feature = "rayon")]
+pub use rayon_impl::ParIter;
+pub use robin_hood::RobinHoodMap;
+pub use set::HashSet;
+
+/// построитель хэшера по умолчанию
+#[cfg(feature = "std")]
+pub type DefaultHashBuilder = RandomState;
+
+/// без `std` случайного хэшера нет: у этого типа нет значений, и построитель хэшера
+/// передается явно через `with_hasher`
+#[cfg(not(feature = "std"))]
+pub enum DefaultHashBuilder {}
+
+// флаг занятого слота имеет выставленный старший бит, а в остальных семи битах
+// хранит верхние биты хэша ключа, поэтому большинство несовпадающих ключей
+// отсеивается сравнением флагов без обращения к самим слотам
+const EMPTY: u8 = 0;
+const DELETED: u8 = 1;
+
+fn is_full(flag: u8) -> bool {
+    flag & 0x80 != 0
+}
+
+fn full_flag(hash: usize) -> u8 {
+    0x80 | (hash >> (usize::BITS - 7)) as u8
+}
+
+// за массивом флагов лежит копия первых `GROUP_WIDTH` флагов (по модулю размера таблицы),
+// чтобы группу, начинающуюся у конца таблицы, можно было прочитать одной загрузкой
+fn ctrl_len(capacity: usize) -> usize {
+    capacity + GROUP_WIDTH
+}
+
+// доля слотов, которую можно занять до роста таблицы
+const DEFAULT_MAX_LOAD_FACTOR: f64 = 0.875;
+
+// сколько элементов помещается после первого роста пустой таблицы, чтобы первые
+// вставки не перевыделяли память на каждом шаге
+const MIN_GROW_CAPACITY: usize = 8;
+
+// занятыми могут быть не больше `factor` слотов, и хотя бы один слот всегда остается пустым,
+// на нем заканчивается пробирование
+fn max_load(capacity: usize, factor: f64) -> usize {
+    ((capacity as f64 * factor) as usize).min(capacity.saturating_sub(1))
+}
+
+// минимальное количество слотов, в которое помещается `items` элементов
+fn checked_exact_buckets_for(items: usize, factor: f64) -> Option<usize> {
+    if items == 0 {
+        return Some(0);
+    }
+
+    let buckets = items as f64 / factor;
+
+    if buckets >= usize::MAX as f64 {
+        return None;
+    }
+
+    // поправка на округление при делении, `f64::ceil` без `std` недоступен
+    let mut buckets = buckets as usize;
+
+    while max_load(buckets, factor) < items {
+        buckets = buckets.checked_add(1)?;
+    }
+
+    Some(buckets)
+}
+
+fn checked_buckets_for(items: usize, factor: f64) -> Option<usize> {
+    if items == 0 {
+        return Some(0);
+    }
+
+    checked_exact_buckets_for(items, factor)?.checked_next_power_of_two()
+}
+
+// то же самое, но с округлением до степени двойки, чтобы рост таблицы был амортизированным.
+// слишком большой размер приводит к панике, а не к переполнению в ноль
+fn buckets_for(items: usize, factor: f64) -> usize {
+    checked_buckets_for(items, factor).expect("capacity overflow")
+}
+
+// `index % capacity`. размер таблицы - степень двойки везде, кроме `reserve_exact`,
+// и тогда деление заменяется маской
+fn wrap(index: usize, capacity: usize) -> usize {
+    if capacity.is_power_of_two() {
+        index & (capacity - 1)
+    } else {
+        index % capacity
+    }
+}
+
+// слот, с которого начинается пробирование для `hash`. для степени двойки это младшие биты
+// хэша, для размера после `reserve_exact` вместо деления используется fastrange Лемира:
+// старшие биты произведения `hash * capacity`. старшие 7 бит хэша уже ушли во флаг,
+// поэтому в произведение идут биты под ними, иначе соседние слоты получали бы одинаковые флаги
+fn start_index(hash: usize, capacity: usize) -> usize {
+    if capacity.is_power_of_two() {
+        hash & (capacity - 1)
+    } else {
+        (((hash << 7) as u128 * capacity as u128) >> usize::BITS) as usize
+    }
+}
+
+// флаги слотов хранятся отдельным массивом байт сразу за слотами,
+// поэтому не добавляют к каждому слоту выравнивание ключа
+struct Slot<K, V> {
+    key: K,
+    value: V,
+}
+
+// результат хэшера дополнительно перемешивается умножением на 2^64 / φ со сворачиванием
+// старших бит в младшие, чтобы даже хэшер, возвращающий ключ как есть, не сводил ключи
+// с шагом в размер таблицы в один слот
+fn make_hash<S: BuildHasher, Q: Hash + ?Sized>(hash_builder: &S, key: &Q) -> usize {
+    mix_hash(hash_builder.hash_one(key))
+}
+
+fn mix_hash(hash: u64) -> usize {
+    let hash = hash.wrapping_mul(0x9E3779B97F4A7C15);
+    (hash ^ (hash >> 32)) as usize
+}
+
+// подсказка процессору заранее подтянуть адрес в кэш, пока сравниваются флаги группы.
+// на поведение не влияет, только на скорость, а на других архитектурах ничего не делает
+#[cfg(target_arch = "x86_64")]
+fn prefetch<T>(ptr: *const T) {
+    use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+    unsafe { _mm_prefetch::<_MM_HINT_T0>(ptr as *const i8) }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn prefetch<T>(_ptr: *const T) {}
+
+/// ошибка, возвращаемая `HashMap::try_reserve`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TryReserveError {
+    /// требуемое количество слотов не помещается в адресное пространство
+    CapacityOverflow,
+    /// аллокатор не смог выделить память
+    AllocError { layout: Layout },
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryReserveError::CapacityOverflow => f.write_str("capacity overflow"),
+            TryReserveError::AllocError { layout } => {
+                write!(f, "memory allocation of {} bytes failed", layout.size())
+            }
+        }
+    }
+}
+
+impl core::error::Error for TryReserveError {}
+
+fn handle_reserve_error(error: TryReserveError) -> ! {
+    match error {
+        TryReserveError::CapacityOverflow => panic!("capacity overflow"),
+        TryReserveError::AllocError { layout } => handle_alloc_error(layout),
+    }
+}
+
+// вызывает `f` для `value` при выходе из области видимости, в том числе при панике,
+// так что таблица остается согласованной, даже если хэшер или `clone` паникуют посреди работы
+struct Guard<T, F: FnMut(&mut T)> {
+    value: T,
+    f: F,
+}
+
+fn guard<T, F: FnMut(&mut T)>(value: T, f: F) -> Guard<T, F> {
+    Guard { value, f }
+}
+
+impl<T, F: FnMut(&mut T)> Deref for Guard<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T, F: FnMut(&mut T)> DerefMut for Guard<T, F> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T, F: FnMut(&mut T)> Drop for Guard<T, F> {
+    fn drop(&mut self) {
+        (self.f)(&mut self.value);
+    }
+}
+
+// одно выделение памяти на таблицу: `capacity` слотов, за ними флаги.
+// возвращает раскладку и смещение массива флагов
+fn table_layout<K, V>(capacity: usize) -> Option<(Layout, usize)> {
+    let slots = Layout::array::<Slot<K, V>>(capacity).ok()?;
+    let ctrl = Layout::array::<u8>(ctrl_len(capacity)).ok()?;
+    slots.extend(ctrl).ok()
+}
+
+// указатели на слоты и флаги внутри одного выделения
+type Table<K, V> = (NonNull<Slot<K, V>>, NonNull<u8>);
+
+unsafe fn try_allocate<K, V>(capacity: usize) -> Result<Table<K, V>, TryReserveError> {
+    if capacity == 0 {
+        return Ok((NonNull::dangling(), NonNull::dangling()));
+    }
+
+    let (layout, ctrl_offset) =
+        table_layout::<K, V>(capacity).ok_or(TryReserveError::CapacityOverflow)?;
+    // нулевые флаги означают, что все слоты пусты
+    let ptr = NonNull::new(alloc_zeroed(layout)).ok_or(TryReserveError::AllocError { layout })?;
+
+    Ok((
+        ptr.cast(),
+        NonNull::new_unchecked(ptr.as_ptr().add(ctrl_offset)),
+    ))
+}
+
+unsafe fn allocate<K, V>(capacity: usize) -> Table<K, V> {
+    try_allocate(capacity).unwrap_or_else(|error| handle_reserve_error(error))
+}
+
+unsafe fn deallocate<K, V>(slots: NonNull<Slot<K, V>>, capacity: usize) {
+    if capacity == 0 {
+        return;
+    }
+
+    let (layout, _) = table_layout::<K, V>(capacity).unwrap();
+    dealloc(slots.as_ptr() as *mut u8, layout);
+}
+
+/// хэш-таблица с открытой адресацией, ключи хэшируются с помощью `S`. результат хэшера
+/// перемешивается умножением на 2^64 / φ, старшие 7 бит перемешанного хэша идут во флаг
+/// слота, а слот, с которого начинается поиск, для размера в степень двойки берется из
+/// младших бит, для остальных размеров (после `reserve_exact` или по `GrowthPolicy`) -
+/// через fastrange. флаги лежат отдельным массивом и сравниваются группами по 16, на x86 за
+/// одну инструкцию SSE2. группы перебираются с треугольным шагом, а для размеров не в степень
+/// двойки и с фичей `linear-probing` подряд
+pub struct HashMap<K, V, S = DefaultHashBuilder> {
+    slots: NonNull<Slot<K, V>>,
+    ctrl: NonNull<u8>,
+    items: usize,
+    tombstones: usize,
+    capacity: usize,
+    max_load_factor: f64,
+    // без политики таблица растет по `max_load_factor`
+    growth: Option<&'static dyn GrowthPolicy>,
+    hash_builder: S,
+    marker: PhantomData<(K, V)>,
+}
+
+#[cfg(feature = "std")]
+impl<K, V> HashMap<K, V, RandomState> {
+    pub fn new() -> HashMap<K, V, RandomState> {
+        Self::with_hasher(RandomState::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> HashMap<K, V, RandomState> {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+
+    pub fn with_growth_policy(policy: &'static dyn GrowthPolicy) -> HashMap<K, V, RandomState> {
+        Self::with_growth_policy_and_hasher(policy, RandomState::new())
+    }
+}
+
+impl<K, V, S> HashMap<K, V, S> {
+    pub fn with_hasher(hash_builder: S) -> HashMap<K, V, S> {
+        HashMap {
+            slots: NonNull::dangling(),
+            ctrl: NonNull::dangling(),
+            items: 0,
+            tombstones: 0,
+            capacity: 0,
+            max_load_factor: DEFAULT_MAX_LOAD_FACTOR,
+            growth: None,
+            hash_builder,
+            marker: PhantomData,
+        }
+    }
+
+    /// размер таблицы будет выбирать `policy`, а не `max_load_factor`
+    pub fn with_growth_policy_and_hasher(
+        policy: &'static dyn GrowthPolicy,
+        hash_builder: S,
+    ) -> HashMap<K, V, S> {
+        let mut map = Self::with_hasher(hash_builder);
+        map.growth = Some(policy);
+        map
+    }
+
+    unsafe fn new_inner(capacity: usize, hash_builder: S) -> HashMap<K, V, S> {
+        let capacity = buckets_for(capacity, DEFAULT_MAX_LOAD_FACTOR);
+        debug_assert!(capacity.is_power_of_two() || capacity == 0);
+        let (slots, ctrl) = allocate(capacity);
+
+        HashMap {
+            slots,
+            ctrl,
+            capacity,
+            max_load_factor: DEFAULT_MAX_LOAD_FACTOR,
+            growth: None,
+            items: 0,
+            tombstones: 0,
+            hash_builder,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> HashMap<K, V, S> {
+        unsafe { Self::new_inner(capacity, hash_builder) }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    unsafe fn slot(&self, index: usize) -> &Slot<K, V> {
+        &*self.slots.as_ptr().add(index)
+    }
+
+    unsafe fn slot_mut(&mut self, index: usize) -> &mut Slot<K, V> {
+        &mut *self.slots.as_ptr().add(index)
+    }
+
+    unsafe fn flag(&self, index: usize) -> u8 {
+        *self.ctrl.as_ptr().add(index)
+    }
+
+    // записывает флаг вместе со всеми его копиями в хвосте массива флагов
+    unsafe fn set_flag(&mut self, index: usize, flag: u8) {
+        let ctrl = self.ctrl.as_ptr();
+        *ctrl.add(index) = flag;
+
+        let mut mirror = index;
+
+        while mirror < GROUP_WIDTH {
+            *ctrl.add(self.capacity + mirror) = flag;
+            mirror += self.capacity;
+        }
+    }
+
+    unsafe fn group(&self, index: usize) -> Group {
+        Group::load(self.ctrl.as_ptr().add(index))
+    }
+
+    // пробирование группами: возвращает начала групп, которые вместе покрывают всю таблицу.
+    // бит `bit` группы `pos` соответствует слоту `(pos + bit) % capacity`.
+    // группы перебираются с шагом в треугольные числа, что разбивает скопления занятых слотов
+    // и при размере в степень двойки обходит каждую группу ровно один раз. для таблиц другого
+    // размера (после `reserve_exact`) и с фичей `linear-probing` шаг всегда в одну группу
+    fn prob_seq(&self, hash: usize) -> impl Iterator<Item = usize> {
+        let capacity = self.capacity;
+        let triangular = !cfg!(feature = "linear-probing") && capacity.is_power_of_two();
+        let mut pos = start_index(hash, capacity);
+        let mut stride = 0;
+
+        (0..capacity.div_ceil(GROUP_WIDTH)).map(move |_| {
+            let current = pos;
+            stride = if triangular {
+                stride + GROUP_WIDTH
+            } else {
+                GROUP_WIDTH
+            };
+            pos = wrap(pos + stride, capacity);
+            current
+        })
+    }
+
+    fn find_insert_slot(&self, hash: usize) -> usize {
+        for pos in self.prob_seq(hash) {
+            prefetch(unsafe { self.slots.as_ptr().add(pos) });
+            let group = unsafe { self.group(pos) };
+
+            if let Some(bit) = group.match_empty_or_deleted().lowest_set_bit() {
+                return wrap(pos + bit, self.capacity);
+            }
+        }
+
+        unreachable!();
+    }
+
+    // записывает элемент с хэшем `hash` в свободный слот `index`. в пустом слоте значения
+    // никогда не было, а из удаленного оно уже перемещено `take`, поэтому старое содержимое
+    // перезаписывается без освобождения
+    unsafe fn insert_in_slot(&mut self, index: usize, hash: usize, key: K, value: V) -> &mut V {
+        let slot = self.slots.as_ptr().add(index);
+        slot.write(Slot { key, value });
+
+        if self.flag(index) == DELETED {
+            self.tombstones -= 1;
+        }
+
+        self.set_flag(index, full_flag(hash));
+        self.items += 1;
+
+        &mut (*slot).value
+    }
+
+    // вынимает элемент из занятого слота `index`: слот перемещается вместе с ключом,
+    // после чего перестает быть занятым и больше никогда не читается как живое значение
+    unsafe fn take(&mut self, index: usize) -> Slot<K, V> {
+        debug_assert!(is_full(self.flag(index)), "slot {} is not occupied", index);
+        debug_assert!(self.items > 0);
+
+        let slot = core::ptr::read(self.slot(index));
+        self.erase(index);
+        self.items -= 1;
+        slot
+    }
+
+    // поиск проходит группу насквозь, только если в ней нет пустых слотов. если слот
+    // никогда не входил в такую группу, т.е. вокруг него нет `GROUP_WIDTH` непустых слотов
+    // подряд, то ни одна цепочка пробирования через него не проходит, и он становится пустым,
+    // иначе слот помечается удаленным
+    unsafe fn erase(&mut self, index: usize) {
+        let flag = if self.capacity <= GROUP_WIDTH {
+            // любая группа покрывает всю таблицу, в которой всегда есть пустой слот
+            EMPTY
+        } else {
+            let before = wrap(index + self.capacity - GROUP_WIDTH, self.capacity);
+            let empty_before = self.group(before).match_empty();
+            let empty_after = self.group(index).match_empty();
+
+            if empty_before.leading_zeros() + empty_after.trailing_zeros() >= GROUP_WIDTH {
+                self.tombstones += 1;
+                DELETED
+            } else {
+                EMPTY
+            }
+        };
+
+        self.set_flag(index, flag);
+    }
+
+    fn raw_iter(&self) -> RawIter<K, V> {
+        RawIter::new(self.slots, self.ctrl, self.capacity, self.items)
+    }
+
+    /// флаги и слоты таблицы как есть, для собственного обхода без итератора
+    pub fn raw_slots(&self) -> RawSlots<'_, K, V> {
+        unsafe {
+            RawSlots {
+                flags: core::slice::from_raw_parts(self.ctrl.as_ptr(), self.capacity),
+                slots: core::slice::from_raw_parts(
+                    self.slots.as_ptr() as *const RawSlot<K, V>,
+                    self.capacity,
+                ),
+            }
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.raw_iter(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            inner: self.raw_iter(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    pub fn into_keys(self) -> IntoKeys<K, V> {
+        IntoKeys {
+            inner: self.into_iter(),
+        }
+    }
+
+    pub fn into_values(self) -> IntoValues<K, V> {
+        IntoValues {
+            inner: self.into_iter(),
+        }
+    }
+
+    /// преобразует значения, сохраняя ключи. хэши ключей не меняются, поэтому
+    /// элементы остаются в тех же слотах таблицы того же размера и не перехэшируются
+    pub fn map_values<U, F: FnMut(V) -> U>(self, mut f: F) -> HashMap<K, U, S> {
+        let this = ManuallyDrop::new(self);
+        let mut map = HashMap {
+            slots: NonNull::dangling(),
+            ctrl: NonNull::dangling(),
+            items: 0,
+            tombstones: 0,
+            capacity: 0,
+            max_load_factor: this.max_load_factor,
+            growth: this.growth,
+            hash_builder: unsafe { core::ptr::read(&this.hash_builder) },
+            marker: PhantomData,
+        };
+
+        if this.capacity == 0 {
+            return map;
+        }
+
+        // старые слоты переходят к итератору: если `f` запаникует, он освободит
+        // непреобразованные элементы, а новая таблица - уже записанные
+        let mut old = IntoIter {
+            inner: this.raw_iter(),
+            slots: this.slots,
+            capacity: this.capacity,
+        };
+
+        unsafe {
+            (map.slots, map.ctrl) = allocate(this.capacity);
+            map.capacity = this.capacity;
+
+            for idx in 0..this.capacity {
+                if !is_full(this.flag(idx)) {
+                    map.set_flag(idx, this.flag(idx));
+                }
+            }
+
+            for slot in &mut old.inner {
+                let idx = slot.offset_from(this.slots.as_ptr()) as usize;
+                let Slot { key, value } = core::ptr::read(slot);
+
+                map.slots.as_ptr().add(idx).write(Slot {
+                    key,
+                    value: f(value),
+                });
+                map.items += 1;
+                map.set_flag(idx, this.flag(idx));
+            }
+        }
+
+        map.tombstones = this.tombstones;
+        map
+    }
+
+    /// удаляет все элементы, но оставляет выделенную под слоты память
+    pub fn clear(&mut self) {
+        unsafe {
+            if core::mem::needs_drop::<Slot<K, V>>() {
+                for slot in self.raw_iter() {
+                    slot.drop_in_place();
+                }
+            }
+
+            if self.capacity != 0 {
+                self.ctrl
+                    .as_ptr()
+                    .write_bytes(EMPTY, ctrl_len(self.capacity));
+            }
+        }
+
+        self.items = 0;
+        self.tombstones = 0;
+    }
+
+    pub fn drain(&mut self) -> Drain<'_, K, V, S> {
+        let slots = core::mem::replace(&mut self.slots, NonNull::dangling());
+        let ctrl = core::mem::replace(&mut self.ctrl, NonNull::dangling());
+        let capacity = core::mem::replace(&mut self.capacity, 0);
+        let items = core::mem::replace(&mut self.items, 0);
+        self.tombstones = 0;
+
+        Drain {
+            inner: RawIter::new(slots, ctrl, capacity, items),
+            slots,
+            ctrl,
+            capacity,
+            map: self,
+        }
+    }
+
+    /// оставляет только те элементы, для которых `f` вернула `true`
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        for idx in 0..self.capacity {
+            unsafe {
+                if !is_full(self.flag(idx)) {
+                    continue;
+                }
+
+                let slot = self.slot_mut(idx);
+
+                if !f(&slot.key, &mut slot.value) {
+                    drop(self.take(idx));
+                }
+            }
+        }
+    }
+
+    /// лениво удаляет элементы, для которых `pred` вернула `true`, и выдает их
+    pub fn extract_if<F: FnMut(&K, &mut V) -> bool>(
+        &mut self,
+        pred: F,
+    ) -> ExtractIf<'_, K, V, F, S> {
+        ExtractIf {
+            map: self,
+            index: 0,
+            pred,
+        }
+    }
+
+    /// курсор для прохода по таблице с удалением текущего элемента
+    pub fn cursor(&mut self) -> Cursor<'_, K, V, S> {
+        Cursor {
+            map: self,
+            index: 0,
+            current: None,
+        }
+    }
+
+    /// сколько элементов поместится в таблицу без перевыделения памяти
+    pub fn capacity(&self) -> usize {
+        self.max_load_for(self.capacity)
+    }
+
+    fn max_load_for(&self, buckets: usize) -> usize {
+        match self.growth {
+            Some(policy) => policy.max_load(buckets).min(buckets.saturating_sub(1)),
+            None => max_load(buckets, self.max_load_factor),
+        }
+    }
+
+    // минимальное количество слотов под `items` элементов. политика задает только
+    // неубывающую `max_load`, поэтому размер ищется двоичным поиском
+    fn checked_exact_buckets(&self, items: usize) -> Option<usize> {
+        if self.growth.is_none() {
+            return checked_exact_buckets_for(items, self.max_load_factor);
+        }
+
+        if items == 0 {
+            return Some(0);
+        }
+
+        let mut high = items.checked_add(1)?;
+
+        while self.max_load_for(high) < items {
+            high = high.checked_mul(2)?;
+        }
+
+        // в `low` слотах `items` элементов не помещается: их не больше `items`,
+        // а занятым всегда остается меньше, чем слотов, либо этот размер уже проверен
+        let mut low = (high / 2).min(items);
+
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+
+            if self.max_load_for(middle) < items {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+
+        Some(high)
+    }
+
+    // размер для `items` элементов при `resize` и `shrink_to`: без политики
+    // округляется до степени двойки
+    fn checked_buckets(&self, items: usize) -> Option<usize> {
+        match self.growth {
+            Some(_) => self.checked_exact_buckets(items),
+            None => checked_buckets_for(items, self.max_load_factor),
+        }
+    }
+
+    // размер после роста под `needed` элементов
+    fn checked_grow_buckets(&self, needed: usize) -> Option<usize> {
+        match self.growth {
+            Some(policy) => {
+                let buckets = policy.next_capacity(self.capacity, needed);
+
+                if self.max_load_for(buckets) >= needed {
+                    Some(buckets)
+                } else {
+                    self.checked_exact_buckets(needed)
+                }
+            }
+            // таблица после `reserve_exact` может быть не степенью двойки, и округление
+            // `needed` тогда дало бы рост меньше чем вдвое
+            None => {
+                let needed = needed
+                    .max(self.capacity().saturating_mul(2))
+                    .max(MIN_GROW_CAPACITY);
+                checked_buckets_for(needed, self.max_load_factor)
+            }
+        }
+    }
+
+    /// сколько слотов выделит `with_capacity(items)` с загрузкой по умолчанию,
+    /// без создания самой таблицы
+    pub fn capacity_for(items: usize) -> usize {
+        buckets_for(items, DEFAULT_MAX_LOAD_FACTOR)
+    }
+
+    /// сколько всего слотов выделено, часть из них всегда остается пустой
+    pub fn buckets(&self) -> usize {
+        self.capacity
+    }
+
+    /// сколько байт занимает выделение под слоты и флаги, без учета памяти,
+    /// на которую ссылаются сами ключи и значения
+    pub fn memory_usage(&self) -> usize {
+        if self.capacity == 0 {
+            return 0;
+        }
+
+        let (layout, _) = table_layout::<K, V>(self.capacity).unwrap();
+        layout.size()
+    }
+
+    /// доля занятых слотов
+    pub fn load_factor(&self) -> f64 {
+        if self.capacity == 0 {
+            0.0
+        } else {
+            self.items as f64 / self.capacity as f64
+        }
+    }
+
+    pub fn max_load_factor(&self) -> f64 {
+        self.max_load_factor
+    }
+
+    pub fn len(&self) -> usize {
+        self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items == 0
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> HashMap<K, V, S> {
+    fn find<Q>(&self, hash: usize, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq,
+    {
+        self.find_by(hash, |stored| stored.borrow() == key)
+            .map(|(idx, _)| idx)
+    }
+
+    // ищет слот с ключом, для которого `is_match` вернет `true`, и дополнительно считает,
+    // сколько слотов цепочки пробирования предшествует найденному
+    fn find_by<F: FnMut(&K) -> bool>(
+        &self,
+        hash: usize,
+        mut is_match: F,
+    ) -> Option<(usize, usize)> {
+        if self.capacity == 0 {
+            return None;
+        }
+
+        let flag = full_flag(hash);
+
+        for (probe, pos) in self.prob_seq(hash).enumerate() {
+            // скорее всего ключ лежит в первом слоте группы
+            prefetch(unsafe { self.slots.as_ptr().add(pos) });
+            let group = unsafe { self.group(pos) };
+
+            for bit in group.match_byte(flag) {
+                let idx = wrap(pos + bit, self.capacity);
+
+                if is_match(unsafe { &self.slot(idx).key }) {
+                    return Some((idx, probe * GROUP_WIDTH + bit));
+                }
+            }
+
+            if group.match_empty().any_bit_set() {
+                return None;
+            }
+        }
+
+        None
+    }
+
+    /// сколько слотов пришлось просмотреть до слота с ключом, 0 означает, что ключ лежит
+    /// в своем начальном слоте. помогает оценить скопления ключей в реальной нагрузке
+    pub fn probe_length<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = make_hash(&self.hash_builder, key);
+        self.find_by(hash, |stored| stored.borrow() == key)
+            .map(|(_, probe)| probe)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = make_hash(&self.hash_builder, key);
+        self.find(hash, key)
+            .map(|idx| unsafe { &self.slot(idx).value })
+    }
+
+    /// возвращает вместе со значением ключ, хранящийся в таблице
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = make_hash(&self.hash_builder, key);
+        self.find(hash, key).map(|idx| {
+            let slot = unsafe { self.slot(idx) };
+            (&slot.key, &slot.value)
+        })
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = make_hash(&self.hash_builder, key);
+        self.find(hash, key).is_some()
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = make_hash(&self.hash_builder, key);
+        self.find(hash, key)
+            .map(move |idx| unsafe { &mut self.slot_mut(idx).value })
+    }
+
+    /// применяет `f` к значению ключа, если он есть. возвращает, был ли ключ
+    pub fn update<Q, F>(&mut self, key: &Q, f: F) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+        F: FnOnce(&mut V),
+    {
+        match self.get_mut(key) {
+            Some(value) => {
+                f(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// одновременно выдает изменяемые ссылки на значения нескольких ключей.
+    /// возвращает `None`, если хотя бы одного ключа нет или ключи повторяются
+    pub fn get_many_mut<Q, const N: usize>(&mut self, keys: [&Q; N]) -> Option<[&mut V; N]>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let mut indices = [0; N];
+
+        for (idx, key) in keys.iter().enumerate() {
+            let hash = make_hash(&self.hash_builder, *key);
+            let slot = self.find(hash, *key)?;
+
+            if indices[..idx].contains(&slot) {
+                return None;
+            }
+
+            indices[idx] = slot;
+        }
+
+        // все индексы различны, а таблица заимствована целиком,
+        // поэтому ссылки указывают на непересекающиеся слоты
+        let slots = self.slots.as_ptr();
+        Some(indices.map(|idx| unsafe { &mut (*slots.add(idx)).value }))
+    }
+
+    /// `get_many_mut` для двух ключей
+    pub fn get2_mut<Q>(&mut self, a: &Q, b: &Q) -> Option<(&mut V, &mut V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let [a, b] = self.get_many_mut([a, b])?;
+        Some((a, b))
+    }
+
+    /// перезапись значения существующего ключа никогда не растит и не перехэширует таблицу:
+    /// место выделяется только после того, как поиск не нашел ключ
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = make_hash(&self.hash_builder, &key);
+
+        if let Some(idx) = self.find(hash, &key) {
+            let slot = unsafe { self.slot_mut(idx) };
+            Some(core::mem::replace(&mut slot.value, value))
+        } else {
+            self.reserve(1);
+            self.insert_inner(hash, key, value);
+            None
+        }
+    }
+
+    /// вставляет все пары, заранее выделив место по нижней оценке их количества,
+    /// значения существующих ключей перезаписываются. возвращает, сколько пар
+    /// попало на уже существующий ключ
+    pub fn insert_many<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) -> usize {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+
+        let mut overwritten = 0;
+
+        for (key, value) in iter {
+            if self.insert(key, value).is_some() {
+                overwritten += 1;
+            }
+        }
+
+        overwritten
+    }
+
+    /// как `collect`, но вместе с таблицей возвращает, сколько пар перезаписали
+    /// значение уже встреченного ключа
+    pub fn collect_counting<I: IntoIterator<Item = (K, V)>>(iter: I) -> (HashMap<K, V, S>, usize)
+    where
+        S: Default,
+    {
+        let mut map = HashMap::with_hasher(S::default());
+        let overwritten = map.insert_many(iter);
+        (map, overwritten)
+    }
+
+    /// как `insert_many`, но для копируемых пар: место выделяется сразу под весь срез,
+    /// а каждая пара вставляется за один проход пробирования. перезаписываемое значение
+    /// не нужно освобождать, поэтому оно просто затирается
+    pub fn extend_from_slice(&mut self, pairs: &[(K, V)])
+    where
+        K: Copy,
+        V: Copy,
+    {
+        self.reserve(pairs.len());
+
+        for &(key, value) in pairs {
+            let hash = make_hash(&self.hash_builder, &key);
+
+            match self.find_or_find_insert_slot(hash, |stored| *stored == key) {
+                Ok(index) => unsafe { self.slot_mut(index).value = value },
+                Err(index) => unsafe {
+                    self.insert_in_slot(index, hash, key, value);
+                },
+            }
+        }
+    }
+
+    /// переносит все элементы `other` в эту таблицу, перезаписывая значения совпавших ключей.
+    /// `other` остается пустой, но сохраняет выделенную память
+    pub fn append(&mut self, other: &mut HashMap<K, V, S>) {
+        self.reserve(other.len());
+
+        for (key, value) in other.drain() {
+            self.insert(key, value);
+        }
+    }
+
+    fn insert_inner(&mut self, hash: usize, key: K, value: V) {
+        let index = self.find_insert_slot(hash);
+
+        unsafe {
+            self.insert_in_slot(index, hash, key, value);
+        }
+    }
+
+    // за один проход ищет либо слот с ключом, либо слот, в который ключ можно вставить
+    fn find_or_find_insert_slot<F: FnMut(&K) -> bool>(
+        &self,
+        hash: usize,
+        mut is_match: F,
+    ) -> Result<usize, usize> {
+        let flag = full_flag(hash);
+        let mut insert_slot = None;
+
+        for pos in self.prob_seq(hash) {
+            prefetch(unsafe { self.slots.as_ptr().add(pos) });
+            let group = unsafe { self.group(pos) };
+
+            for bit in group.match_byte(flag) {
+                let idx = wrap(pos + bit, self.capacity);
+
+                if is_match(unsafe { &self.slot(idx).key }) {
+                    return Ok(idx);
+                }
+            }
+
+            if insert_slot.is_none() {
+                insert_slot = group
+                    .match_empty_or_deleted()
+                    .lowest_set_bit()
+                    .map(|bit| wrap(pos + bit, self.capacity));
+            }
+
+            if group.match_empty().any_bit_set() {
+                break;
+            }
+        }
+
+        Err(insert_slot.unwrap())
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        let hash = make_hash(&self.hash_builder, &key);
+
+        // таблица растет только при вставке во `VacantEntry`, которая тогда заново ищет слот.
+        // в пустой таблице искать негде, и слот будет найден уже после роста
+        let found = if self.capacity == 0 {
+            Err(0)
+        } else {
+            self.find_or_find_insert_slot(hash, |stored| *stored == key)
+        };
+
+        match found {
+            Ok(index) => Entry::Occupied(OccupiedEntry { map: self, index }),
+            Err(index) => Entry::Vacant(VacantEntry {
+                map: self,
+                hash,
+                key,
+                index,
+            }),
+        }
+    }
+
+    /// слоты для пачки ключей с одним выделением памяти на всю пачку
+    pub fn entries<I: IntoIterator<Item = K>>(
+        &mut self,
+        keys: I,
+    ) -> Entries<'_, K, V, I::IntoIter, S> {
+        let keys = keys.into_iter();
+        self.reserve(keys.size_hint().0);
+
+        Entries { map: self, keys }
+    }
+
+    /// поиск по заранее посчитанному хэшу и произвольному сравнению ключей.
+    /// хэш должен быть получен как `map.hasher().hash_one(key)`
+    pub fn raw_entry(&self) -> RawEntryBuilder<'_, K, V, S> {
+        RawEntryBuilder { map: self }
+    }
+
+    /// как `raw_entry`, но позволяет изменить или вставить найденный элемент
+    pub fn raw_entry_mut(&mut self) -> RawEntryBuilderMut<'_, K, V, S> {
+        RawEntryBuilderMut { map: self }
+    }
+
+    /// возвращает значение по ключу, вычисляя и вставляя его при отсутствии,
+    /// таблица при этом пробируется один раз
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, f: F) -> &mut V {
+        self.entry(key).or_insert_with(f)
+    }
+
+    /// как `get_or_insert_with`, но значение может не построиться: тогда ошибка
+    /// возвращается, а в таблицу ничего не вставляется
+    pub fn get_or_try_insert_with<F, E>(&mut self, key: K, f: F) -> Result<&mut V, E>
+    where
+        F: FnOnce() -> Result<V, E>,
+    {
+        match self.entry(key) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => Ok(entry.insert(f()?)),
+        }
+    }
+
+    /// изменяет значение через `modify`, если ключ есть, иначе вставляет `default()`.
+    /// то же, что `entry(key).and_modify(modify).or_insert_with(default)`
+    pub fn modify_or_insert<F, G>(&mut self, key: K, modify: F, default: G)
+    where
+        F: FnOnce(&mut V),
+        G: FnOnce() -> V,
+    {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => modify(entry.get_mut()),
+            Entry::Vacant(entry) => {
+                entry.insert(default());
+            }
+        }
+    }
+
+    /// вставляет значение, только если ключа еще нет. иначе ничего не меняет и возвращает
+    /// ошибку с найденным элементом и отвергнутым значением
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<&mut V, OccupiedError<'_, K, V, S>> {
+        match self.entry(key) {
+            Entry::Occupied(entry) => Err(OccupiedError { entry, value }),
+            Entry::Vacant(entry) => Ok(entry.insert(value)),
+        }
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// как `remove`, но возвращает вместе со значением и хранившийся в таблице ключ
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = make_hash(&self.hash_builder, key);
+        let idx = self.find(hash, key)?;
+        let Slot { key, value } = unsafe { self.take(idx) };
+
+        Some((key, value))
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self
+            .items
+            .checked_add(additional)
+            .expect("capacity overflow");
+
+        if needed + self.tombstones <= self.capacity() {
+            return;
+        }
+
+        // пустых слотов почти не осталось из-за надгробий. перехэшировать на месте стоит,
+        // только если таблица заполнена не больше чем наполовину: иначе после каждого
+        // удаления следующая вставка снова упиралась бы в предел и обходила всю таблицу
+        if needed <= self.capacity() / 2 {
+            self.rehash_in_place();
+        } else {
+            let capacity = self
+                .checked_grow_buckets(needed)
+                .expect("capacity overflow");
+            self.rehash(capacity);
+        }
+    }
+
+    /// то же, что `reserve`, но возвращает, сколько элементов теперь помещается
+    /// в таблицу, т.е. не меньше `len() + additional`
+    pub fn reserve_returning(&mut self, additional: usize) -> usize {
+        self.reserve(additional);
+        self.capacity()
+    }
+
+    /// в отличие от `reserve` не округляет количество слотов до степени двойки,
+    /// поэтому не оставляет запаса под дальнейшие вставки
+    pub fn reserve_exact(&mut self, additional: usize) {
+        let needed = self
+            .items
+            .checked_add(additional)
+            .expect("capacity overflow");
+
+        if needed + self.tombstones <= self.capacity() {
+            return;
+        }
+
+        // как в `reserve`, но рост без запаса: на место хотя бы одного элемента больше,
+        // чем помещается сейчас
+        if needed <= self.capacity() / 2 {
+            self.rehash_in_place();
+        } else {
+            let capacity = self
+                .checked_exact_buckets(needed.max(self.capacity() + 1))
+                .expect("capacity overflow");
+            self.rehash(capacity);
+        }
+    }
+
+    /// как `reserve`, но вместо аварийного завершения при нехватке памяти или
+    /// переполнении размера возвращает ошибку, оставляя таблицу нетронутой
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let needed = self
+            .items
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+
+        if needed + self.tombstones <= self.capacity() {
+            return Ok(());
+        }
+
+        if needed <= self.capacity() / 2 {
+            self.rehash_in_place();
+        } else {
+            let capacity = self
+                .checked_grow_buckets(needed)
+                .ok_or(TryReserveError::CapacityOverflow)?;
+            self.try_rehash(capacity)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn resize(&mut self, new_size: usize) {
+        assert!(
+            new_size >= self.items,
+            "the new size is less than count of items"
+        );
+
+        let capacity = self.checked_buckets(new_size).expect("capacity overflow");
+
+        // таблица уже нужного размера, перехэшировать незачем
+        if capacity != self.capacity {
+            self.rehash(capacity);
+        }
+
+        debug_assert!(
+            self.capacity.is_power_of_two() || self.capacity == 0 || self.growth.is_some()
+        );
+    }
+
+    fn rehash(&mut self, capacity: usize) {
+        if let Err(error) = self.try_rehash(capacity) {
+            handle_reserve_error(error);
+        }
+    }
+
+    // переносит все элементы в новый массив из `capacity` слотов
+    fn try_rehash(&mut self, capacity: usize) -> Result<(), TryReserveError> {
+        unsafe {
+            let (slots, ctrl) = try_allocate(capacity)?;
+            let old_slots = core::mem::replace(&mut self.slots, slots);
+            let old_ctrl = core::mem::replace(&mut self.ctrl, ctrl);
+            let old_capacity = core::mem::replace(&mut self.capacity, capacity);
+            self.items = 0;
+            self.tombstones = 0;
+
+            // элементы перемещаются побитово, а старый массив освобождается
+            // без вызова деструкторов, так что каждое значение остается в единственном экземпляре.
+            // если хэшер запаникует, еще не перенесенные элементы освобождаются вместе
+            // со старым массивом, а в таблице остаются перенесенные
+            let mut moved = guard(0, |&mut moved| {
+                for idx in moved..old_capacity {
+                    if is_full(*old_ctrl.as_ptr().add(idx)) {
+                        old_slots.as_ptr().add(idx).drop_in_place();
+                    }
+                }
+
+                deallocate(old_slots, old_capacity);
+            });
+
+            for idx in 0..old_capacity {
+                *moved = idx + 1;
+
+                if is_full(*old_ctrl.as_ptr().add(idx)) {
+                    let slot = core::ptr::read(old_slots.as_ptr().add(idx));
+                    let hash = make_hash(&self.hash_builder, &slot.key);
+                    let index = self.find_insert_slot(hash);
+                    self.slots.as_ptr().add(index).write(slot);
+                    self.set_flag(index, full_flag(hash));
+                    self.items += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // переставляет элементы внутри тех же слотов, избавляясь от надгробий.
+    // сначала все занятые слоты помечаются удаленными, а удаленные пустыми, затем каждый
+    // помеченный элемент переносится в первый подходящий для него слот: если там пусто,
+    // элемент просто перемещается, если там еще один непереставленный элемент, они меняются
+    // местами и перестановка продолжается для того, что оказался на месте текущего
+    fn rehash_in_place(&mut self) {
+        let capacity = self.capacity;
+
+        unsafe {
+            for idx in 0..capacity {
+                let flag = if is_full(self.flag(idx)) {
+                    DELETED
+                } else {
+                    EMPTY
+                };
+
+                self.set_flag(idx, flag);
+            }
+
+            // непереставленные элементы помечены удаленными. если хэшер запаникует,
+            // они освобождаются, а их слоты становятся пустыми
+            let mut map = guard(self, |map| {
+                map.tombstones = 0;
+
+                for idx in 0..map.capacity {
+                    if map.flag(idx) == DELETED {
+                        map.set_flag(idx, EMPTY);
+                        map.slots.as_ptr().add(idx).drop_in_place();
+                        map.items -= 1;
+                    }
+                }
+            });
+
+            // номер группы в цепочке пробирования, из которой слот `pos` виден впервые
+            let probe_index = |hash: usize, pos: usize| {
+                wrap(pos + capacity - start_index(hash, capacity), capacity) / GROUP_WIDTH
+            };
+
+            'slots: for idx in 0..capacity {
+                if map.flag(idx) != DELETED {
+                    continue;
+                }
+
+                loop {
+                    let hash = make_hash(&map.hash_builder, &map.slot(idx).key);
+                    let new_idx = map.find_insert_slot(hash);
+
+                    // элемент и так находится в первой группе, где для него есть место
+                    if probe_index(hash, idx) == probe_index(hash, new_idx) {
+                        map.set_flag(idx, full_flag(hash));
+                        continue 'slots;
+                    }
+
+                    let previous = map.flag(new_idx);
+                    map.set_flag(new_idx, full_flag(hash));
+
+                    if previous == EMPTY {
+                        map.set_flag(idx, EMPTY);
+                        core::ptr::copy_nonoverlapping(
+                            map.slots.as_ptr().add(idx),
+                            map.slots.as_ptr().add(new_idx),
+                            1,
+                        );
+                        continue 'slots;
+                    }
+
+                    core::ptr::swap_nonoverlapping(
+                        map.slots.as_ptr().add(idx),
+                        map.slots.as_ptr().add(new_idx),
+                        1,
+                    );
+                }
+            }
+        }
+    }
+
+    /// задает долю слотов, которую можно занять до роста таблицы, от 0.1 до 0.95.
+    /// если элементов уже больше, чем позволяет новая доля, таблица сразу растет
+    pub fn set_max_load_factor(&mut self, factor: f64) {
+        assert!(
+            self.growth.is_none(),
+            "the load factor is defined by the growth policy"
+        );
+        assert!(
+            (0.1..=0.95).contains(&factor),
+            "max load factor must be within 0.1..=0.95"
+        );
+
+        self.max_load_factor = factor;
+        self.reserve(0);
+    }
+
+    /// уменьшает таблицу до минимального размера, в котором помещаются все элементы
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// уменьшает таблицу, оставляя место как минимум под `min_capacity` элементов.
+    /// если таблица уже не больше нужного, ничего не делает
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let size = self.items.max(min_capacity);
+
+        if self.checked_buckets(size).expect("capacity overflow") < self.capacity {
+            self.resize(size);
+        }
+    }
+}
+
+impl<K: Ord, V, S> HashMap<K, V, S> {
+    /// пары в порядке возрастания ключей, не зависящем от хэшера и истории вставок.
+    /// ссылки на все элементы сначала собираются в вектор и сортируются, это стоит
+    /// O(n log n) времени и O(n) дополнительной памяти
+    pub fn iter_sorted(&self) -> alloc::vec::IntoIter<(&K, &V)> {
+        let mut pairs: Vec<_> = self.iter().collect();
+        pairs.sort_unstable_by_key(|&(key, _)| key);
+        pairs.into_iter()
+    }
+}
+
+impl<K, V, S> Drop for HashMap<K, V, S> {
+    fn drop(&mut self) {
+        if self.capacity != 0 {
+            unsafe {
+                if core::mem::needs_drop::<Slot<K, V>>() {
+                    for slot in self.raw_iter() {
+                        slot.drop_in_place();
+                    }
+                }
+
+                deallocate(self.slots, self.capacity);
+            }
+        }
+    }
+}
+
+impl<K, V, S: Default> Default for HashMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Clone, V: Clone, S: Clone> Clone for HashMap<K, V, S> {
+    // копия сохраняет расположение слотов (вместе с надгробиями) и хэшер,
+    // поэтому ничего не нужно перехэшировать
+    fn clone(&self) -> Self {
+        let mut map = HashMap {
+            slots: NonNull::dangling(),
+            ctrl: NonNull::dangling(),
+            items: 0,
+            tombstones: 0,
+            capacity: 0,
+            max_load_factor: self.max_load_factor,
+            growth: self.growth,
+            hash_builder: self.hash_builder.clone(),
+            marker: PhantomData,
+        };
+
+        if self.capacity == 0 {
+            return map;
+        }
+
+        unsafe {
+            (map.slots, map.ctrl) = allocate(self.capacity);
+            map.capacity = self.capacity;
+
+            for idx in 0..self.capacity {
+                if is_full(self.flag(idx)) {
+                    let slot = self.slot(idx);
+                    map.slots.as_ptr().add(idx).write(Slot {
+                        key: slot.key.clone(),
+                        value: slot.value.clone(),
+                    });
+                    map.items += 1;
+                }
+
+                // флаг и счетчик обновляются после записи значения, чтобы при панике
+                // в `clone` копия освободила ровно уже записанные слоты
+                map.set_flag(idx, self.flag(idx));
+            }
+        }
+
+        map.tombstones = self.tombstones;
+        map
+    }
+}
+
+// таблицы равны, если содержат одни и те же пары, расположение слотов значения не имеет
+impl<K: Hash + Eq, V: PartialEq, S: BuildHasher> PartialEq for HashMap<K, V, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K: Hash + Eq, V: Eq, S: BuildHasher> Eq for HashMap<K, V, S> {}
+
+// хэши пар складываются, поэтому результат не зависит от порядка слотов. пары хэшируются
+// фиксированным `FxHasher`, а не `S`: равные таблицы с разными случайными хэшерами
+// должны давать одинаковый хэш
+impl<K: Hash, V: Hash, S> Hash for HashMap<K, V, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let sum = self.iter().fold(0u64, |sum, pair| {
+            let mut hasher = hash::FxHasher::default();
+            pair.hash(&mut hasher);
+            sum.wrapping_add(hasher.finish())
+        });
+
+        state.write_usize(self.items);
+        state.write_u64(sum);
+    }
+}
+
+impl<K: Debug, V: Debug, S> Debug for HashMap<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for HashMap<K, V, S> {
+    // у `ExactSizeIterator` нижняя оценка размера точная, поэтому память
+    // выделяется один раз
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = HashMap::with_hasher(S::default());
+        map.insert_many(iter);
+        map
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for HashMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.insert_many(iter);
+    }
+}
+
+impl<'a, K, V, S> Extend<(&'a K, &'a V)> for HashMap<K, V, S>
+where
+    K: Hash + Eq + Copy,
+    V: Copy,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|(&key, &value)| (key, value)));
+    }
+}
+
+impl<K, Q, V, S> Index<&Q> for HashMap<K, V, S>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: ?Sized + Hash + Eq,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    /// # Panics
+    ///
+    /// паникует, если ключа нет в таблице
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+impl<K, V, S> IntoIterator for HashMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        // память и значения переходят к итератору, который их и освободит
+        let map = ManuallyDrop::new(self);
+        drop(unsafe { core::ptr::read(&map.hash_builder) });
+
+        IntoIter {
+            inner: map.raw_iter(),
+            slots: map.slots,
+            capacity: map.capacity,
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a HashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut HashMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+unsafe impl<K: Send, V: Send, S: Send> Send for HashMap<K, V, S> {}
+
+// таблица владеет слотами так же, как `Box<[Slot]>`, и не имеет внутренней
+// изменяемости: через `&self` слоты только читаются, поэтому общая ссылка
+// безопасна, если безопасны общие ссылки на ключи, значения и хешер
+unsafe impl<K: Sync, V: Sync, S: Sync> Sync for HashMap<K, V, S> {}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::{
+        is_full, make_hash, start_index, table_layout, wrap, HashMap, Slot, TryReserveError,
+        DELETED, GROUP_WIDTH,
+    };
+    use crate::hash::FxBuildHasher;
+    use rand::rngs::StdRng;
+    use rand::{random, Rng, SeedableRng};
+    use std::cell::Cell;
+    use std::collections::HashMap as StdMap;
+    use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+    use std::panic::{self, AssertUnwindSafe};
+    use test::Bencher;
+
+    // детерминированный FNV-1a с финальным перемешиванием из murmur3
+    struct FnvHasher(u64);
+
+    impl Default for FnvHasher {
+        fn default() -> Self {
+            FnvHasher(0xcbf29ce484222325)
+        }
+    }
+
+    impl Hasher for FnvHasher {
+        fn finish(&self) -> u64 {
+            let mut hash = self.0;
+            hash = (hash ^ (hash >> 33)).wrapping_mul(0xff51afd7ed558ccd);
+            hash = (hash ^ (hash >> 33)).wrapping_mul(0xc4ceb9fe1a85ec53);
+            hash ^ (hash >> 33)
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &byte in bytes {
+                self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100000001b3);
+            }
+        }
+    }
+
+    type FnvState = BuildHasherDefault<FnvHasher>;
+
+    // хэшер, возвращающий целочисленный ключ как есть
+    #[derive(Default)]
+    struct IdentityHasher(u64);
+
+    impl Hasher for IdentityHasher {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &byte in bytes {
+                self.0 = (self.0 << 8) | byte as u64;
+            }
+        }
+
+        fn write_usize(&mut self, value: usize) {
+            self.0 = value as u64;
+        }
+    }
+
+    type IdentityState = BuildHasherDefault<IdentityHasher>;
+
+    // подбирает `count` ключей, которые попадают в слот `bucket` таблицы на `buckets` слотов.
+    // годится и для `RobinHoodMap`, у которой слот тоже остаток от деления хэша
+    pub(crate) fn keys_in_bucket<S: BuildHasher>(
+        hash_builder: &S,
+        buckets: usize,
+        bucket: usize,
+        count: usize,
+    ) -> Vec<usize> {
+        (0..)
+            .filter(|key| make_hash(hash_builder, key) % buckets == bucket)
+            .take(count)
+            .collect()
+    }
+
+    #[test]
+    fn empty_hashmap() {
+        let hashmap: HashMap<usize, f32> = HashMap::new();
+        assert_eq!(hashmap.capacity(), 0);
+        assert_eq!(hashmap.get(&0), None);
+    }
+
+    #[test]
+    fn with_capacity_zero() {
+        let mut hashmap: HashMap<usize, f32> = HashMap::with_capacity(0);
+        assert_eq!(hashmap.capacity(), 0);
+        assert_eq!(hashmap.buckets(), 0);
+        assert_eq!(hashmap.get(&0), None);
+
+        hashmap.insert(0, 0.5);
+        assert_eq!(hashmap.get(&0).copied(), Some(0.5));
+    }
+
+    #[test]
+    fn default() {
+        #[derive(Default)]
+        struct Cache {
+            values: HashMap<usize, String>,
+        }
+
+        let hashmap: HashMap<usize, f32> = HashMap::default();
+        assert_eq!(hashmap.capacity(), 0);
+        assert!(hashmap.is_empty());
+
+        let cache = Cache::default();
+        assert_eq!(cache.values.capacity(), 0);
+    }
+
+    #[test]
+    fn resize() {
+        let mut hashmap: HashMap<usize, f32> = HashMap::with_capacity(1);
+        hashmap.insert(0, 0.1);
+        hashmap.insert(1, 0.2);
+        assert_eq!(hashmap.get(&0).copied(), Some(0.1));
+        assert_eq!(hashmap.get(&1).copied(), Some(0.2));
+    }
+
+    #[test]
+    fn resize_same_size() {
+        let mut hashmap: HashMap<usize, usize> = (0..100).map(|key| (key, key)).collect();
+        let slots = hashmap.slots;
+
+        hashmap.resize(100);
+        hashmap.resize(110);
+        hashmap.reserve(10);
+        assert_eq!(hashmap.slots, slots);
+        assert_eq!(hashmap.buckets(), 128);
+
+        // уменьшение по-прежнему работает
+        let mut small: HashMap<usize, usize> = (0..10).map(|key| (key, key)).collect();
+        small.reserve(1000);
+        small.resize(10);
+        assert_eq!(small.buckets(), 16);
+        assert!((0..10).all(|key| small[&key] == key));
+    }
+
+    #[test]
+    fn resize_moves_owned_values() {
+        let mut hashmap: HashMap<String, String> = HashMap::new();
+
+        for key in 0..20 {
+            hashmap.insert(key.to_string(), format!("value {}", key));
+        }
+
+        hashmap.reserve(100);
+        hashmap.shrink_to_fit();
+
+        for key in 0..20 {
+            assert_eq!(hashmap[&key.to_string()], format!("value {}", key));
+        }
+    }
+
+    #[test]
+    fn capacity() {
+        let mut hashmap: HashMap<usize, f32> = HashMap::with_capacity(12);
+        assert_eq!(hashmap.capacity(), 14);
+        assert_eq!(hashmap.buckets(), 16);
+        hashmap.insert(15, 0.21);
+        assert_eq!(hashmap.capacity(), 14);
+
+        for key in 0..13 {
+            hashmap.insert(key, key as f32);
+        }
+
+        assert_eq!(hashmap.len(), 14);
+        assert_eq!(hashmap.buckets(), 16);
+        hashmap.insert(14, 0.0);
+        assert_eq!(hashmap.buckets(), 32);
+    }
+
+    #[test]
+    fn with_capacity_without_grow() {
+        // размер таблицы остается степенью двойки, но вмещает не меньше запрошенного
+        for requested in 0..300 {
+            let mut hashmap: HashMap<usize, usize> = HashMap::with_capacity(requested);
+            let buckets = hashmap.buckets();
+            assert!(hashmap.capacity() >= requested);
+
+            for key in 0..requested {
+                hashmap.insert(key, key);
+            }
+
+            assert_eq!(hashmap.buckets(), buckets, "with_capacity({})", requested);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn reserve_overflow() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+        hashmap.insert(1, 1);
+        hashmap.reserve(usize::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn reserve_exact_overflow() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+        hashmap.insert(1, 1);
+        hashmap.reserve_exact(usize::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn with_capacity_overflow() {
+        let _: HashMap<usize, usize> = HashMap::with_capacity(usize::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn with_capacity_overflow_below_max() {
+        let _: HashMap<usize, usize> = HashMap::with_capacity(usize::MAX - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn with_capacity_layout_overflow() {
+        // количество слотов еще помещается в `usize`, а их размер в байтах уже нет
+        let _: HashMap<usize, usize> = HashMap::with_capacity(usize::MAX / 16);
+    }
+
+    #[test]
+    fn reserve_exact_with_tombstones() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+        hashmap.reserve_exact(1000);
+        let buckets = hashmap.buckets();
+
+        hashmap.extend((0..1000).map(|key| (key, key)));
+        let mut removed = 0;
+
+        while hashmap.tombstones == 0 {
+            hashmap.remove(&removed);
+            removed += 1;
+        }
+
+        // заполненная таблица не перехэшируется на месте, а растет до точного размера
+        hashmap.reserve_exact(removed);
+        assert_eq!(hashmap.tombstones, 0);
+        assert!(hashmap.buckets() > buckets);
+        assert!(!hashmap.buckets().is_power_of_two());
+        assert!(hashmap.capacity() > 1000);
+        assert!((removed..1000).all(|key| hashmap[&key] == key));
+    }
+
+    #[test]
+    fn reserve_exact() {
+        let mut amortized: HashMap<usize, usize> = HashMap::new();
+        let mut exact: HashMap<usize, usize> = HashMap::new();
+
+        amortized.reserve(100);
+        exact.reserve_exact(100);
+
+        assert_eq!(amortized.buckets(), 128);
+        assert_eq!(amortized.capacity(), 112);
+        assert_eq!(exact.buckets(), 115);
+        assert_eq!(exact.capacity(), 100);
+
+        for key in 0..100 {
+            exact.insert(key, key);
+        }
+
+        assert_eq!(exact.buckets(), 115);
+
+        for key in 0..100 {
+            assert_eq!(exact.get(&key).copied(), Some(key));
+        }
+
+        // дальше таблица растет как минимум вдвое
+        exact.insert(100, 100);
+        assert_eq!(exact.buckets(), 256);
+    }
+
+    #[test]
+    fn try_reserve() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+        hashmap.insert(1, 1);
+
+        assert_eq!(
+            hashmap.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(
+            hashmap.try_reserve(usize::MAX / 16),
+            Err(TryReserveError::CapacityOverflow)
+        );
+
+        assert_eq!(hashmap.try_reserve(100), Ok(()));
+        assert_eq!(hashmap.buckets(), 128);
+        assert_eq!(hashmap.get(&1).copied(), Some(1));
+    }
+
+    #[test]
+    fn memory_layout() {
+        // слот занимает размер пары, еще один байт на слот уходит на флаг
+        // и `GROUP_WIDTH` байт на копию начала массива флагов
+        assert_eq!(std::mem::size_of::<Slot<usize, u8>>(), 16);
+
+        let (layout, ctrl_offset) = table_layout::<usize, u8>(64).unwrap();
+        assert_eq!(ctrl_offset, 64 * 16);
+        assert_eq!(layout.size(), 64 * 17 + 16);
+    }
+
+    #[test]
+    fn memory_against_inline_flags() {
+        // слот до выноса флагов в отдельный массив
+        #[allow(dead_code)]
+        struct InlineSlot<K, V> {
+            flag: u8,
+            key: K,
+            value: V,
+        }
+
+        fn usage<V: Default>() -> (usize, usize) {
+            let mut hashmap: HashMap<usize, V> = HashMap::new();
+            hashmap.extend((0..1000).map(|key| (key, V::default())));
+
+            let inline = hashmap.buckets() * std::mem::size_of::<InlineSlot<usize, V>>();
+            (inline, hashmap.memory_usage())
+        }
+
+        // у `u8` флаг помещался в хвост выравнивания рядом со значением, поэтому
+        // отдельный массив флагов обходится на байт на слот дороже
+        assert_eq!(usage::<u8>(), (2048 * 16, 2048 * 17 + 16));
+
+        // а значению на все выравнивание флаг добавлял целое слово
+        assert_eq!(usage::<u64>(), (2048 * 24, 2048 * 17 + 16));
+    }
+
+    // сколько групп приходится просмотреть, чтобы убедиться в отсутствии ключа
+    fn miss_probe_length<V>(hashmap: &HashMap<usize, V>, key: usize) -> usize {
+        let hash = make_hash(&hashmap.hash_builder, &key);
+
+        hashmap
+            .prob_seq(hash)
+            .position(|pos| unsafe { hashmap.group(pos) }.match_empty().any_bit_set())
+            .unwrap()
+            + 1
+    }
+
+    #[test]
+    fn tombstones_rehash_in_place() {
+        // таблица заполнена меньше чем наполовину, поэтому надгробия убираются на месте
+        let mut hashmap: HashMap<usize, usize> = HashMap::with_capacity(896);
+
+        for key in 0..400 {
+            hashmap.insert(key, key);
+        }
+
+        for round in 0..400 {
+            for key in round * 50..round * 50 + 50 {
+                assert_eq!(hashmap.remove(&key), Some(key));
+            }
+
+            for key in (round + 8) * 50..(round + 9) * 50 {
+                hashmap.insert(key, key);
+            }
+
+            assert!(hashmap.items + hashmap.tombstones <= 896);
+        }
+
+        assert_eq!(hashmap.buckets(), 1024);
+        assert_eq!(hashmap.len(), 400);
+
+        for key in 20_000..20_400 {
+            assert_eq!(hashmap.get(&key), Some(&key));
+        }
+
+        let max_miss = (0..1000)
+            .map(|key| miss_probe_length(&hashmap, key))
+            .max()
+            .unwrap();
+
+        // без перехэширования надгробия вытеснили бы все пустые слоты,
+        // и промах обходил бы все 64 группы
+        assert!(max_miss <= 32, "{}", max_miss);
+    }
+
+    #[test]
+    fn full_table_churn() {
+        // перехэширование заново хэширует все ключи, а удаление и вставка - по одному
+        #[derive(Default)]
+        struct CountingState {
+            calls: Cell<usize>,
+        }
+
+        impl BuildHasher for CountingState {
+            type Hasher = std::collections::hash_map::DefaultHasher;
+
+            fn build_hasher(&self) -> Self::Hasher {
+                self.calls.set(self.calls.get() + 1);
+                Default::default()
+            }
+        }
+
+        let mut hashmap: HashMap<usize, usize, CountingState> =
+            HashMap::with_capacity_and_hasher(14_336, CountingState::default());
+        let mut key = 0;
+
+        while hashmap.len() < hashmap.capacity() {
+            hashmap.insert(key, key);
+            key += 1;
+        }
+
+        let mut rehashes = 0;
+
+        for removed in 0..20_000 {
+            let calls = hashmap.hash_builder.calls.get();
+
+            assert_eq!(hashmap.remove(&removed), Some(removed));
+            hashmap.insert(key, key);
+            key += 1;
+
+            if hashmap.hash_builder.calls.get() - calls > 3 {
+                rehashes += 1;
+            }
+        }
+
+        // полная таблица один раз вырастает, а дальше между перехэшированиями
+        // надгробия успевают занять половину слотов
+        assert!(rehashes <= 2, "{}", rehashes);
+        assert_eq!(hashmap.len(), 14_336);
+        assert!((20_000..key).all(|key| hashmap[&key] == key));
+    }
+
+    #[test]
+    fn reserve_after_deletes() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::with_capacity(896);
+
+        for key in 0..896 {
+            hashmap.insert(key, key);
+        }
+
+        for key in 0..880 {
+            hashmap.remove(&key);
+        }
+
+        for reserve in [HashMap::reserve, HashMap::reserve_exact] {
+            reserve(&mut hashmap, 16);
+            assert!(hashmap.len() + hashmap.tombstones + 16 <= hashmap.capacity());
+        }
+
+        for key in 1000..1016 {
+            hashmap.insert(key, key);
+        }
+
+        assert_eq!(hashmap.len(), 32);
+        assert_eq!(hashmap.buckets(), 1024);
+        assert!((880..896).chain(1000..1016).all(|key| hashmap[&key] == key));
+    }
+
+    #[test]
+    fn max_load_factor() {
+        let mut hashmap: HashMap<usize, usize> = (0..100).map(|key| (key, key)).collect();
+        assert_eq!(hashmap.buckets(), 128);
+        assert_eq!(hashmap.max_load_factor(), 0.875);
+        assert_eq!(hashmap.load_factor(), 100.0 / 128.0);
+
+        hashmap.set_max_load_factor(0.5);
+        assert_eq!(hashmap.buckets(), 256);
+        assert_eq!(hashmap.capacity(), 128);
+        assert!(hashmap.load_factor() <= 0.5);
+
+        // растет таблица тоже по новой доле
+        for key in 100..129 {
+            hashmap.insert(key, key);
+        }
+
+        assert_eq!(hashmap.buckets(), 512);
+        assert!((0..129).all(|key| hashmap[&key] == key));
+
+        hashmap.set_max_load_factor(0.95);
+        assert_eq!(hashmap.buckets(), 512);
+        assert_eq!(hashmap.capacity(), 486);
+    }
+
+    #[test]
+    #[should_panic(expected = "max load factor must be within 0.1..=0.95")]
+    fn max_load_factor_bounds() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+        hashmap.set_max_load_factor(1.0);
+    }
+
+    #[test]
+    fn zero_sized_values() {
+        assert_eq!(std::mem::size_of::<Slot<usize, ()>>(), 8);
+
+        let mut hashmap: HashMap<usize, ()> = HashMap::new();
+
+        for key in 0..100 {
+            assert_eq!(hashmap.insert(key, ()), None);
+        }
+
+        assert_eq!(hashmap.insert(5, ()), Some(()));
+        assert_eq!(hashmap.len(), 100);
+
+        for key in 0..50 {
+            assert_eq!(hashmap.remove(&key), Some(()));
+        }
+
+        assert_eq!(hashmap.len(), 50);
+        assert_eq!(hashmap.iter().count(), 50);
+        assert!(hashmap.keys().all(|&key| key >= 50));
+
+        // и ключ, и значение нулевого размера: массив слотов пуст, остаются только флаги
+        let mut unit: HashMap<(), ()> = HashMap::new();
+        unit.insert((), ());
+        unit.insert((), ());
+        assert_eq!(unit.len(), 1);
+        assert_eq!(unit.iter().count(), 1);
+        assert_eq!(unit.remove(&()), Some(()));
+        assert!(unit.is_empty());
+        assert_eq!(unit.into_iter().count(), 0);
+    }
+
+    #[test]
+    fn grow_by_load_factor() {
+        let mut hashmap: HashMap<usize, f32> = HashMap::with_capacity(7);
+        let keys = keys_in_bucket(&hashmap.hash_builder, hashmap.buckets(), 0, 9);
+
+        for &key in &keys[..8] {
+            hashmap.insert(key, key as f32);
+        }
+
+        assert_eq!(hashmap.buckets(), 16);
+        assert_eq!(hashmap.len(), 8);
+        assert_eq!(hashmap.get(&keys[8]), None);
+
+        for &key in &keys[..8] {
+            assert_eq!(hashmap.get(&key).copied(), Some(key as f32));
+        }
+    }
+
+    #[test]
+    fn collision() {
+        let mut hashmap: HashMap<usize, f32> = HashMap::with_capacity(4);
+        let keys = keys_in_bucket(&hashmap.hash_builder, hashmap.buckets(), 0, 2);
+        hashmap.insert(keys[0], 0.1);
+        hashmap.insert(keys[1], 0.2);
+
+        assert_eq!(hashmap.get(&keys[0]).copied(), Some(0.1));
+        assert_eq!(hashmap.get(&keys[1]).copied(), Some(0.2));
+    }
+
+    #[test]
+    fn probe_length() {
+        let mut hashmap: HashMap<usize, f32> = HashMap::with_capacity(28);
+        let keys = keys_in_bucket(&hashmap.hash_builder, hashmap.buckets(), 5, 3);
+        hashmap.insert(keys[0], 0.1);
+        hashmap.insert(keys[1], 0.2);
+
+        assert_eq!(hashmap.probe_length(&keys[0]), Some(0));
+        assert_eq!(hashmap.probe_length(&keys[1]), Some(1));
+        assert_eq!(hashmap.probe_length(&keys[2]), None);
+    }
+
+    #[test]
+    fn collision_remove() {
+        let mut hashmap: HashMap<usize, f32> = HashMap::with_capacity(4);
+        let keys = keys_in_bucket(&hashmap.hash_builder, hashmap.buckets(), 0, 3);
+        hashmap.insert(keys[0], 0.1);
+        hashmap.insert(keys[1], 0.2);
+        hashmap.insert(keys[2], 0.3);
+
+        hashmap.remove(&keys[1]);
+
+        assert_eq!(hashmap.get(&keys[0]).copied(), Some(0.1));
+        assert_eq!(hashmap.get(&keys[2]).copied(), Some(0.3));
+    }
+
+    #[test]
+    fn collision_remove_first() {
+        let mut hashmap: HashMap<usize, f32> = HashMap::with_capacity(4);
+        let keys = keys_in_bucket(&hashmap.hash_builder, hashmap.buckets(), 0, 2);
+        hashmap.insert(keys[0], 0.1);
+        hashmap.insert(keys[1], 0.2);
+
+        assert_eq!(hashmap.remove(&keys[0]), Some(0.1));
+
+        assert_eq!(hashmap.get(&keys[0]), None);
+        assert_eq!(hashmap.get(&keys[1]).copied(), Some(0.2));
+    }
+
+    #[test]
+    fn collision_remove_middle() {
+        // таблица больше группы, и в окне вокруг удаляемого слота нет пустых слотов,
+        // поэтому в середине цепочки остается надгробие
+        let mut hashmap: HashMap<usize, f32> = HashMap::with_capacity(56);
+        let keys = keys_in_bucket(&hashmap.hash_builder, hashmap.buckets(), 0, 21);
+        assert!(hashmap.buckets() > GROUP_WIDTH);
+
+        for (idx, &key) in keys[..20].iter().enumerate() {
+            hashmap.insert(key, idx as f32);
+        }
+
+        assert_eq!(hashmap.remove(&keys[5]), Some(5.0));
+        assert_eq!(hashmap.tombstones, 1);
+        assert_eq!(hashmap.get(&keys[5]), None);
+
+        for (idx, &key) in keys[..20].iter().enumerate().filter(|&(idx, _)| idx != 5) {
+            assert_eq!(hashmap.get(&key).copied(), Some(idx as f32));
+        }
+
+        hashmap.insert(keys[20], 20.0); // переиспользует удаленный слот
+        assert_eq!(hashmap.tombstones, 0);
+        assert_eq!(hashmap.get(&keys[20]).copied(), Some(20.0));
+        assert_eq!(hashmap.get(&keys[19]).copied(), Some(19.0));
+        assert_eq!(hashmap.len(), 20);
+    }
+
+    #[test]
+    fn collision_wraparound() {
+        // цепочка перекидывается через конец таблицы и длиннее одной группы
+        let mut hashmap: HashMap<usize, usize> = HashMap::with_capacity(28);
+        let keys = keys_in_bucket(&hashmap.hash_builder, hashmap.buckets(), 30, 20);
+
+        for &key in &keys {
+            hashmap.insert(key, key);
+        }
+
+        assert_eq!(hashmap.buckets(), 32);
+
+        for &key in keys.iter().step_by(3) {
+            assert_eq!(hashmap.remove(&key), Some(key));
+        }
+
+        for (idx, key) in keys.iter().enumerate() {
+            let expected = if idx % 3 == 0 { None } else { Some(key) };
+            assert_eq!(hashmap.get(key), expected);
+        }
+
+        for &key in keys.iter().step_by(3) {
+            hashmap.insert(key, key + 1);
+        }
+
+        assert_eq!(hashmap.len(), 20);
+        assert_eq!(hashmap.get(&keys[0]).copied(), Some(keys[0] + 1));
+    }
+
+    // надгробие хранит уже перемещенное значение, поэтому повторная вставка пишет в слот
+    // без освобождения старого. утечки и двойные освобождения здесь и в `*_panic_safety`
+    // ловит `cargo +nightly miri test`, под ним эти тесты проходят
+    #[test]
+    fn reinsert_into_tombstone() {
+        let mut hashmap: HashMap<usize, String> = HashMap::with_capacity(28);
+        let keys = keys_in_bucket(&hashmap.hash_builder, hashmap.buckets(), 0, 20);
+
+        for &key in &keys {
+            hashmap.insert(key, key.to_string());
+        }
+
+        for round in 0..3 {
+            assert_eq!(hashmap.remove(&keys[5]), Some(keys[5].to_string()));
+            assert_eq!(hashmap.tombstones, 1);
+
+            hashmap.insert(keys[5], keys[5].to_string());
+            assert_eq!(hashmap.tombstones, 0);
+
+            let old = hashmap.insert(keys[5], format!("{}", round));
+            assert_eq!(old, Some(keys[5].to_string()));
+            hashmap.insert(keys[5], keys[5].to_string());
+        }
+
+        assert_eq!(hashmap.buckets(), 32);
+        assert_eq!(hashmap.len(), 20);
+
+        for &key in &keys {
+            assert_eq!(hashmap.get(&key), Some(&key.to_string()));
+        }
+    }
+
+    // случайные вставки, перезаписи и удаления в небольшом диапазоне ключей сверяются
+    // с `std::collections::HashMap`, а счетчики - с флагами слотов
+    #[test]
+    fn random_ops_accounting() {
+        let mut rng = StdRng::seed_from_u64(66);
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+        let mut reference: StdMap<usize, usize> = StdMap::new();
+        let mut reused_tombstone = false;
+
+        for step in 0..50_000 {
+            let key = rng.gen_range(0, 300);
+
+            if rng.gen_bool(0.6) {
+                let tombstones = hashmap.tombstones;
+                assert_eq!(hashmap.insert(key, step), reference.insert(key, step));
+                reused_tombstone |= hashmap.tombstones < tombstones;
+            } else {
+                assert_eq!(hashmap.remove(&key), reference.remove(&key));
+            }
+
+            assert_eq!(hashmap.len(), reference.len());
+
+            if step % 500 == 0 {
+                let flags: Vec<u8> = (0..hashmap.buckets())
+                    .map(|idx| unsafe { hashmap.flag(idx) })
+                    .collect();
+
+                assert_eq!(
+                    flags.iter().filter(|&&flag| is_full(flag)).count(),
+                    hashmap.len()
+                );
+                assert_eq!(
+                    flags.iter().filter(|&&flag| flag == DELETED).count(),
+                    hashmap.tombstones
+                );
+            }
+        }
+
+        for (key, value) in &reference {
+            assert_eq!(hashmap.get(key), Some(value));
+        }
+
+        assert!(reused_tombstone);
+    }
+
+    thread_local! {
+        // сколько `Tracked` сейчас живо
+        static LIVE: Cell<isize> = const { Cell::new(0) };
+        // через сколько вызовов `clone` или `hash` случится паника
+        static PANIC_IN: Cell<usize> = const { Cell::new(usize::MAX) };
+    }
+
+    fn countdown() {
+        PANIC_IN.with(|left| {
+            left.set(left.get() - 1);
+
+            if left.get() == 0 {
+                left.set(usize::MAX);
+                panic!("countdown");
+            }
+        });
+    }
+
+    fn live() -> isize {
+        LIVE.with(Cell::get)
+    }
+
+    struct Tracked(usize);
+
+    impl Tracked {
+        fn new(value: usize) -> Tracked {
+            LIVE.with(|live| live.set(live.get() + 1));
+            Tracked(value)
+        }
+    }
+
+    impl Clone for Tracked {
+        fn clone(&self) -> Tracked {
+            countdown();
+            Tracked::new(self.0)
+        }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            LIVE.with(|live| live.set(live.get() - 1));
+        }
+    }
+
+    #[derive(Clone, PartialEq, Eq)]
+    struct PanickyKey(usize);
+
+    impl Hash for PanickyKey {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            countdown();
+            self.0.hash(state);
+        }
+    }
+
+    fn tracked_map() -> HashMap<PanickyKey, Tracked> {
+        (0..100)
+            .map(|key| (PanickyKey(key), Tracked::new(key)))
+            .collect()
+    }
+
+    // после паники каждый элемент либо остался в таблице, либо освобожден ровно один раз
+    fn assert_consistent(hashmap: HashMap<PanickyKey, Tracked>) {
+        assert_eq!(live(), hashmap.len() as isize);
+
+        for (key, value) in &hashmap {
+            assert_eq!(hashmap.get(key).map(|value| value.0), Some(value.0));
+        }
+
+        drop(hashmap);
+        assert_eq!(live(), 0);
+    }
+
+    #[test]
+    fn clone_panic_safety() {
+        let hashmap = tracked_map();
+        PANIC_IN.with(|left| left.set(50));
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| hashmap.clone()));
+
+        assert!(result.is_err());
+        assert_consistent(hashmap);
+    }
+
+    #[test]
+    fn resize_panic_safety() {
+        let mut hashmap = tracked_map();
+        PANIC_IN.with(|left| left.set(30));
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| hashmap.reserve(1000)));
+
+        assert!(result.is_err());
+        assert_eq!(hashmap.len(), 29);
+        assert_consistent(hashmap);
+    }
+
+    #[test]
+    fn map_values_panic_safety() {
+        let hashmap = tracked_map();
+        let mut calls = 0;
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            hashmap.map_values(|value| {
+                calls += 1;
+                assert!(calls < 30);
+                value
+            })
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(live(), 0);
+    }
+
+    #[test]
+    fn rehash_in_place_panic_safety() {
+        let mut hashmap = tracked_map();
+        PANIC_IN.with(|left| left.set(30));
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| hashmap.rehash_in_place()));
+
+        assert!(result.is_err());
+        assert!(hashmap.len() < 100);
+        assert_eq!(hashmap.tombstones, 0);
+        assert_consistent(hashmap);
+    }
+
+    #[test]
+    fn overwrite_without_realloc() {
+        let mut hashmap: HashMap<usize, String> = HashMap::with_capacity(100);
+        let capacity = hashmap.capacity();
+
+        for key in 0..capacity {
+            hashmap.insert(key, key.to_string());
+        }
+
+        // таблица заполнена до предела, и любая новая вставка вызовет рост
+        let buckets = hashmap.buckets();
+        let value = hashmap.get(&0).unwrap() as *const String;
+
+        for key in 0..capacity {
+            assert_eq!(
+                hashmap.insert(key, "new".to_string()),
+                Some(key.to_string())
+            );
+        }
+
+        assert_eq!(hashmap.buckets(), buckets);
+        assert_eq!(hashmap.get(&0).unwrap() as *const String, value);
+        assert!(hashmap.values().all(|value| value == "new"));
+
+        hashmap.insert(capacity, "grow".to_string());
+        assert!(hashmap.buckets() > buckets);
+    }
+
+    #[test]
+    fn memory_usage() {
+        let mut hashmap: HashMap<u64, u64> = HashMap::new();
+        assert_eq!(hashmap.memory_usage(), 0);
+
+        hashmap.reserve(100);
+        assert_eq!(hashmap.buckets(), 128);
+        // 128 слотов по 16 байт и 128 + 16 флагов
+        assert_eq!(hashmap.memory_usage(), 128 * 16 + 144);
+
+        for key in 0..200 {
+            hashmap.insert(key, key);
+        }
+
+        assert_eq!(hashmap.buckets(), 256);
+        assert_eq!(hashmap.memory_usage(), 256 * 16 + 272);
+    }
+
+    // кроме проверки адресов ниже невыровненное обращение к слоту поймал бы miri,
+    // под `cargo +nightly miri test` тест проходит
+    #[test]
+    fn over_aligned_values() {
+        #[repr(align(64))]
+        #[derive(Clone, PartialEq, Debug)]
+        struct Aligned(u8, String);
+
+        let mut hashmap: HashMap<u8, Aligned> = HashMap::new();
+
+        for key in 0..200 {
+            hashmap.insert(key, Aligned(key, key.to_string()));
+        }
+
+        for key in (0..200).step_by(3) {
+            assert_eq!(hashmap.remove(&key), Some(Aligned(key, key.to_string())));
+        }
+
+        let cloned = hashmap.clone();
+
+        for (map, count) in [(&hashmap, 133), (&cloned, 133)] {
+            assert_eq!(map.len(), count);
+
+            for (&key, value) in map {
+                assert_eq!(value as *const Aligned as usize % 64, 0);
+                assert_eq!(value, &Aligned(key, key.to_string()));
+            }
+        }
+
+        hashmap.shrink_to_fit();
+        assert_eq!(hashmap[&1].1, "1");
+        assert_eq!(hashmap.get(&0), None);
+    }
+
+    #[test]
+    fn capacity_for() {
+        for items in [0, 1, 7, 8, 100, 896, 897, 12_345] {
+            let mut hashmap: HashMap<usize, usize> = HashMap::with_capacity(items);
+            hashmap.extend((0..items).map(|key| (key, key)));
+
+            assert_eq!(
+                HashMap::<usize, usize>::capacity_for(items),
+                hashmap.buckets()
+            );
+        }
+
+        assert_eq!(HashMap::<usize, usize>::capacity_for(896), 1024);
+        assert_eq!(HashMap::<usize, usize>::capacity_for(897), 2048);
+    }
+
+    #[test]
+    fn reserve_returning() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+
+        for additional in [0, 1, 7, 100, 1000, 12_345] {
+            let capacity = hashmap.reserve_returning(additional);
+            assert!(capacity >= hashmap.len() + additional);
+            assert_eq!(capacity, hashmap.capacity());
+
+            let len = hashmap.len();
+            hashmap.extend((len..len + additional).map(|key| (key, key)));
+        }
+
+        let buckets = hashmap.buckets();
+        assert_eq!(hashmap.reserve_returning(0), hashmap.capacity());
+        assert_eq!(hashmap.buckets(), buckets);
+    }
+
+    #[test]
+    fn first_grow() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+        hashmap.insert(1, 1);
+        assert!(hashmap.capacity() >= 8, "{}", hashmap.capacity());
+
+        let buckets = hashmap.buckets();
+
+        for key in 2..=8 {
+            hashmap.insert(key, key);
+        }
+
+        assert_eq!(hashmap.buckets(), buckets);
+
+        // явный `reserve` на пустой таблице тоже не выделяет меньше
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+        hashmap.reserve(1);
+        assert!(hashmap.capacity() >= 8);
+        hashmap.reserve(0);
+        assert_eq!(hashmap.buckets(), 16);
+    }
+
+    #[test]
+    fn geometric_growth() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+        let mut grows = 0;
+
+        for key in 0..1_000_000 {
+            let buckets = hashmap.buckets();
+            hashmap.insert(key, key);
+
+            if hashmap.buckets() != buckets {
+                // каждый рост как минимум удваивает таблицу
+                assert!(hashmap.buckets() >= buckets * 2);
+                grows += 1;
+            }
+        }
+
+        // от пустой таблицы до 16 слотов, затем удвоения до 2^21
+        assert_eq!(grows, 18);
+        assert_eq!(hashmap.buckets(), 1 << 21);
+    }
+
+    #[test]
+    fn wrap_matches_modulo() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+        let mut sizes = Vec::new();
+
+        for key in 0..5000 {
+            hashmap.insert(key, key);
+            let capacity = hashmap.buckets();
+
+            if sizes.last() != Some(&capacity) {
+                assert!(capacity.is_power_of_two());
+                sizes.push(capacity);
+
+                for hash in (0..10_000).chain([usize::MAX - 1, usize::MAX]) {
+                    let hash = make_hash(hashmap.hasher(), &hash);
+                    assert_eq!(wrap(hash, capacity), hash % capacity);
+                }
+            }
+        }
+
+        assert_eq!(sizes.last(), Some(&8192));
+        assert!((0..5000).all(|key| hashmap[&key] == key));
+
+        // после `reserve_exact` размер произвольный, и остается деление
+        hashmap.reserve_exact(5000);
+        let capacity = hashmap.buckets();
+        assert!(!capacity.is_power_of_two());
+        assert_eq!(wrap(capacity + 3, capacity), 3);
+        assert!((0..5000).all(|key| hashmap[&key] == key));
+    }
+
+    #[test]
+    fn start_index_in_range() {
+        let mut rng = StdRng::seed_from_u64(82);
+
+        for _ in 0..10_000 {
+            let capacity = rng.gen_range(1, 1 << 20);
+            let hash = rng.gen::<usize>();
+            assert!(start_index(hash, capacity) < capacity);
+        }
+
+        for capacity in [1, 3, 115, 1000, 1 << 16] {
+            assert!(start_index(0, capacity) < capacity);
+            assert!(start_index(usize::MAX, capacity) < capacity);
+        }
+
+        // для степени двойки это просто младшие биты
+        assert_eq!(start_index(0x1234_5678, 1 << 12), 0x678);
+    }
+
+    #[test]
+    fn prob_seq_covers_table() {
+        let tables = [1, 8, 16, 32, 64, 256, 1024].map(HashMap::<usize, usize>::with_capacity);
+
+        let mut exact: HashMap<usize, usize> = HashMap::new();
+        exact.reserve_exact(100);
+
+        for hashmap in tables.iter().chain([&exact]) {
+            let capacity = hashmap.buckets();
+
+            for start in [0, 1, 7, capacity - 1] {
+                let mut visited = vec![0; capacity];
+
+                for pos in hashmap.prob_seq(start) {
+                    for bit in 0..GROUP_WIDTH.min(capacity) {
+                        visited[(pos + bit) % capacity] += 1;
+                    }
+                }
+
+                assert!(visited.iter().all(|&count| count > 0), "{}", capacity);
+
+                // ни один слот не проверяется дважды
+                if capacity.is_power_of_two() {
+                    assert!(visited.iter().all(|&count| count == 1));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn contains_key() {
+        let mut hashmap: HashMap<usize, f32> = HashMap::with_capacity(4);
+        let keys = keys_in_bucket(&hashmap.hash_builder, hashmap.buckets(), 0, 3);
+        hashmap.insert(keys[0], 0.1);
+        hashmap.insert(keys[1], 0.2);
+
+        assert!(hashmap.contains_key(&keys[0]));
+        assert!(hashmap.contains_key(&keys[1]));
+        assert!(!hashmap.contains_key(&keys[2]));
+
+        hashmap.remove(&keys[0]);
+
+        assert!(!hashmap.contains_key(&keys[0]));
+        assert!(hashmap.contains_key(&keys[1]));
+    }
+
+    #[test]
+    fn get_many_mut() {
+        let mut hashmap: HashMap<usize, String> = HashMap::new();
+        hashmap.insert(1, "one".to_string());
+        hashmap.insert(2, "two".to_string());
+
+        let [first, second] = hashmap.get_many_mut([&1, &2]).unwrap();
+        std::mem::swap(first, second);
+
+        assert_eq!(hashmap[&1], "two");
+        assert_eq!(hashmap[&2], "one");
+
+        assert!(hashmap.get_many_mut([&1, &1]).is_none());
+        assert!(hashmap.get_many_mut([&1, &3]).is_none());
+        assert!(hashmap.get_many_mut::<usize, 0>([]).is_some());
+    }
+
+    #[test]
+    fn get2_mut() {
+        let mut hashmap: HashMap<usize, String> =
+            (0..10).map(|key| (key, key.to_string())).collect();
+
+        let (first, second) = hashmap.get2_mut(&3, &7).unwrap();
+        std::mem::swap(first, second);
+
+        assert_eq!(hashmap[&3], "7");
+        assert_eq!(hashmap[&7], "3");
+        assert!(hashmap.get2_mut(&3, &3).is_none());
+        assert!(hashmap.get2_mut(&3, &30).is_none());
+        assert!(hashmap.get2_mut(&30, &3).is_none());
+    }
+
+    #[test]
+    fn remove_owned_values() {
+        let mut hashmap: HashMap<usize, String> = HashMap::with_capacity(4);
+        let keys = keys_in_bucket(&hashmap.hash_builder, hashmap.buckets(), 0, 3);
+        hashmap.insert(keys[0], "a".to_string());
+        hashmap.insert(keys[1], "b".to_string());
+        hashmap.insert(keys[2], "c".to_string());
+
+        assert_eq!(hashmap.remove(&keys[1]), Some("b".to_string()));
+        assert_eq!(hashmap.remove(&keys[1]), None);
+        assert_eq!(hashmap.remove(&keys[0]), Some("a".to_string()));
+
+        assert_eq!(hashmap.get(&keys[2]).map(String::as_str), Some("c"));
+        assert_eq!(hashmap.len(), 1);
+    }
+
+    #[test]
+    fn get_key_value() {
+        let mut hashmap: HashMap<String, usize> = HashMap::new();
+        hashmap.insert("key".to_string(), 1);
+
+        let (key, value) = hashmap.get_key_value("key").unwrap();
+        assert_eq!(key, "key");
+        assert_eq!(*value, 1);
+        assert_eq!(hashmap.get_key_value("other"), None);
+    }
+
+    #[test]
+    fn remove_entry() {
+        let mut hashmap: HashMap<String, usize> = HashMap::new();
+        hashmap.insert("one".to_string(), 1);
+        hashmap.insert("two".to_string(), 2);
+
+        assert_eq!(hashmap.remove_entry("one"), Some(("one".to_string(), 1)));
+        assert_eq!(hashmap.remove_entry("one"), None);
+        assert_eq!(hashmap.len(), 1);
+        assert_eq!(hashmap.get("two").copied(), Some(2));
+    }
+
+    #[test]
+    fn is_empty() {
+        let mut hashmap: HashMap<usize, f32> = HashMap::new();
+        assert!(hashmap.is_empty());
+
+        for key in 0..20 {
+            hashmap.insert(key, key as f32);
+        }
+
+        assert!(!hashmap.is_empty());
+
+        for key in 0..19 {
+            hashmap.remove(&key);
+        }
+
+        assert!(!hashmap.is_empty());
+
+        hashmap.remove(&19);
+
+        // в таблице могут остаться надгробия, но элементов в ней нет
+        assert!(hashmap.is_empty());
+    }
+
+    #[test]
+    fn clear() {
+        let mut hashmap: HashMap<usize, String> = HashMap::new();
+
+        for key in 0..100 {
+            hashmap.insert(key, key.to_string());
+        }
+
+        let capacity = hashmap.capacity();
+        hashmap.clear();
+
+        assert_eq!(hashmap.len(), 0);
+        assert_eq!(hashmap.capacity(), capacity);
+        assert_eq!(hashmap.get(&10), None);
+        assert_eq!(hashmap.iter().next(), None);
+
+        for key in 0..100 {
+            hashmap.insert(key, key.to_string());
+        }
+
+        assert_eq!(hashmap.len(), 100);
+        assert_eq!(hashmap.capacity(), capacity);
+        assert_eq!(hashmap.get(&10).map(String::as_str), Some("10"));
+    }
+
+    #[test]
+    fn eq() {
+        let mut first: HashMap<usize, String> = HashMap::new();
+        let mut second: HashMap<usize, String> = HashMap::with_capacity(64);
+
+        for key in 0..20 {
+            first.insert(key, key.to_string());
+        }
+
+        for key in (0..20).rev() {
+            second.insert(key, key.to_string());
+        }
+
+        assert_eq!(first, second);
+
+        second.insert(3, "three".to_string());
+        assert_ne!(first, second);
+
+        second.remove(&3);
+        assert_ne!(first, second);
+
+        first.remove(&3);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn hash() {
+        let hasher = std::collections::hash_map::RandomState::new();
+        let mut first: HashMap<usize, String> = HashMap::new();
+        let mut second: HashMap<usize, String> = HashMap::with_capacity(256);
+
+        for key in 0..100 {
+            first.insert(key, key.to_string());
+        }
+
+        for key in (0..150).rev() {
+            second.insert(key, key.to_string());
+        }
+
+        for key in 100..150 {
+            second.remove(&key);
+        }
+
+        assert_eq!(first, second);
+        assert_ne!(first.buckets(), second.buckets());
+        assert_eq!(hasher.hash_one(&first), hasher.hash_one(&second));
+
+        // равные таблицы совпадают и как элементы множества
+        let mut set = super::HashSet::new();
+        assert!(set.insert(first.clone()));
+        assert!(!set.insert(second.clone()));
+
+        second.insert(7, "seven".to_string());
+        assert_ne!(hasher.hash_one(&first), hasher.hash_one(&second));
+        assert!(set.insert(second));
+    }
+
+    #[test]
+    fn debug() {
+        let mut hashmap: HashMap<usize, &str> = HashMap::new();
+        assert_eq!(format!("{:?}", hashmap), "{}");
+
+        hashmap.insert(1, "one");
+        hashmap.insert(2, "two");
+
+        let debug = format!("{:?}", hashmap);
+        assert!(debug.contains("1: \"one\""));
+        assert!(debug.contains("2: \"two\""));
+    }
+
+    #[test]
+    fn clone() {
+        let mut hashmap: HashMap<usize, String> = HashMap::new();
+
+        for key in 0..20 {
+            hashmap.insert(key, key.to_string());
+        }
+
+        hashmap.remove(&5);
 
-use std::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
-use std::marker::PhantomData;
-use std::ptr::NonNull;
+        let cloned = hashmap.clone();
 
-const EMPTY: u8 = 0;
-const TAKEN: u8 = 1;
-const DELETED: u8 = 2;
+        hashmap.get_mut(&1).unwrap().push('!');
+        hashmap.insert(5, "five".to_string());
+        hashmap.remove(&2);
 
-struct Slot<T> {
-    flag: u8, // не самое оптимальное решение по памяти из-за выравнивания структуры
-    key: usize,
-    value: T,
-}
+        assert_eq!(cloned.len(), 19);
+        assert_eq!(cloned.get(&1).map(String::as_str), Some("1"));
+        assert_eq!(cloned.get(&2).map(String::as_str), Some("2"));
+        assert_eq!(cloned.get(&5), None);
 
-/// простейшая хэш-таблица, ключем которой является `usize` значение, хэш-функция от ключа KEY % MAP_CAPACITY
-pub struct HashMap<V> {
-    slots: NonNull<Slot<V>>,
-    items: usize,
-    capacity: usize,
-    marker: PhantomData<V>,
-}
+        assert_eq!(hashmap.get(&1).map(String::as_str), Some("1!"));
+        assert_eq!(hashmap.get(&5).map(String::as_str), Some("five"));
+    }
 
-impl<V> HashMap<V> {
-    pub fn new() -> HashMap<V> {
-        HashMap {
-            slots: NonNull::dangling(),
-            items: 0,
-            capacity: 0,
-            marker: PhantomData,
-        }
+    #[test]
+    fn from_iter() {
+        let hashmap: HashMap<_, _> = (0..100).map(|i| (i, i as f32)).collect();
+
+        assert_eq!(hashmap.len(), 100);
+        assert_eq!(hashmap.get(&0).copied(), Some(0.0));
+        assert_eq!(hashmap.get(&42).copied(), Some(42.0));
+        assert_eq!(hashmap.get(&99).copied(), Some(99.0));
+        assert_eq!(hashmap.get(&100), None);
+
+        let hashmap: HashMap<_, _> = vec![(1, "a"), (2, "b"), (1, "c")].into_iter().collect();
+
+        assert_eq!(hashmap.len(), 2);
+        assert_eq!(hashmap.get(&1).copied(), Some("c"));
     }
 
-    unsafe fn new_inner(capacity: usize) -> HashMap<V> {
-        let capacity = capacity.next_power_of_two();
-        let layout = Layout::array::<Slot<V>>(capacity).unwrap();
-        let slots = alloc_zeroed(layout) as *mut Slot<V>;
+    #[test]
+    fn extend() {
+        let mut hashmap: HashMap<usize, f32> = (0..10).map(|i| (i, i as f32)).collect();
+        hashmap.extend((5..20).map(|i| (i, i as f32 * 10.0)));
+
+        assert_eq!(hashmap.len(), 20);
+        assert_eq!(hashmap.get(&1).copied(), Some(1.0));
+        assert_eq!(hashmap.get(&5).copied(), Some(50.0));
+        assert_eq!(hashmap.get(&19).copied(), Some(190.0));
+
+        let other: HashMap<usize, f32> = (100..110).map(|i| (i, i as f32)).collect();
+        hashmap.extend(&other);
 
-        if slots.is_null() {
-            handle_alloc_error(layout);
+        assert_eq!(hashmap.len(), 30);
+        assert_eq!(hashmap.get(&105).copied(), Some(105.0));
+        assert_eq!(hashmap.get(&9).copied(), Some(90.0));
+    }
+
+    #[test]
+    fn from_exact_size_iter() {
+        thread_local! {
+            static HASHES: Cell<usize> = const { Cell::new(0) };
         }
 
-        HashMap {
-            slots: NonNull::new_unchecked(slots),
-            capacity,
-            items: 0,
-            marker: PhantomData,
+        #[derive(Default)]
+        struct CountingState;
+
+        impl BuildHasher for CountingState {
+            type Hasher = FnvHasher;
+
+            fn build_hasher(&self) -> FnvHasher {
+                HASHES.with(|hashes| hashes.set(hashes.get() + 1));
+                FnvHasher::default()
+            }
         }
-    }
 
-    pub fn with_capacity(capacity: usize) -> HashMap<V> {
-        unsafe { Self::new_inner(capacity) }
+        let pairs: Vec<(usize, usize)> = (0..1000).map(|key| (key, key)).collect();
+        let hashmap: HashMap<usize, usize, CountingState> = pairs.into_iter().collect();
+
+        // каждый ключ хэширован один раз, т.е. таблица ни разу не перехэшировалась при росте
+        assert_eq!(HASHES.with(Cell::get), 1000);
+        assert_eq!(
+            hashmap.buckets(),
+            HashMap::<usize, usize>::with_capacity(1000).buckets()
+        );
+        assert_eq!(hashmap.len(), 1000);
     }
 
-    // максимально простое линейное пробирование с шагом в единицу
-    fn prob_seq(&self, hash: usize) -> impl Iterator<Item = usize> {
-        let capacity = self.capacity;
-        (0..capacity).map(move |idx| (hash + idx) % capacity)
+    #[test]
+    fn collect_counting() {
+        let pairs = (0..100).chain(0..30).chain(10..20).map(|key| (key, key));
+        let (hashmap, overwritten) = HashMap::<usize, usize>::collect_counting(pairs);
+
+        assert_eq!(hashmap.len(), 100);
+        assert_eq!(overwritten, 40);
+
+        let (hashmap, overwritten) = HashMap::<usize, usize>::collect_counting(Vec::new());
+        assert!(hashmap.is_empty());
+        assert_eq!(overwritten, 0);
+
+        let mut hashmap = hashmap;
+        assert_eq!(hashmap.insert_many([(1, 1), (2, 2), (1, 10)]), 1);
+        assert_eq!(hashmap[&1], 10);
     }
 
-    fn find(&self, key: usize) -> Option<(usize, &mut Slot<V>)> {
-        if self.capacity == 0 {
-            return None;
+    #[test]
+    fn fragment_skips_comparisons() {
+        thread_local! {
+            static COMPARISONS: Cell<usize> = const { Cell::new(0) };
         }
 
-        let hash = key % self.capacity;
-        let slots = self.slots.as_ptr();
-
-        for idx in self.prob_seq(hash) {
-            let slot = unsafe { &mut *slots.add(idx) };
+        #[derive(Eq, Debug)]
+        struct Counted(usize);
 
-            if slot.flag == EMPTY {
-                return None;
+        impl Hash for Counted {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
             }
+        }
 
-            if slot.flag == TAKEN && slot.key == key {
-                return Some((idx, slot));
+        impl PartialEq for Counted {
+            fn eq(&self, other: &Counted) -> bool {
+                COMPARISONS.with(|count| count.set(count.get() + 1));
+                self.0 == other.0
             }
         }
 
-        None
-    }
+        // 7/8 слотов заняты, и цепочки проходят через полные группы чужих ключей
+        let mut hashmap: HashMap<Counted, usize> = HashMap::with_capacity(14_336);
+        let buckets = hashmap.buckets();
 
-    fn find_insert_slot(&self, hash: usize) -> usize {
-        let slots = self.slots.as_ptr();
+        for key in 0..hashmap.capacity() {
+            hashmap.insert(Counted(key), key);
+        }
 
-        for idx in self.prob_seq(hash) {
-            let slot = unsafe { &*slots.add(idx) };
+        assert_eq!(hashmap.buckets(), buckets);
+        COMPARISONS.with(|count| count.set(0));
 
-            if slot.flag != TAKEN {
-                return idx;
-            }
+        for key in 0..10_000 {
+            assert_eq!(hashmap.get(&Counted(key)), Some(&key));
         }
 
-        unreachable!();
-    }
+        // ключ сравнивается только при совпадении 7 бит фрагмента, и кроме искомого
+        // это случается примерно для 1 из 128 соседей
+        let hits = COMPARISONS.with(|count| count.replace(0));
+        assert!(hits < 10_000 + 1000, "{}", hits);
 
-    pub fn get<'a>(&'a self, key: usize) -> Option<&'a V> {
-        self.find(key).map(|(_, slot)| &slot.value)
-    }
+        for key in 100_000..110_000 {
+            assert_eq!(hashmap.get(&Counted(key)), None);
+        }
 
-    pub fn get_mut<'a>(&'a mut self, key: usize) -> Option<&'a mut V> {
-        self.find(key).map(|(_, slot)| &mut slot.value)
+        // без фрагментов промах сравнивался бы со всеми ключами каждой просмотренной
+        // группы, т.е. хотя бы с 14 на поиск, а так выходит около 0.2
+        let misses = COMPARISONS.with(Cell::get);
+        assert!(misses < 5000, "{}", misses);
     }
 
-    pub fn insert(&mut self, key: usize, value: V) -> Option<V> {
-        if let Some((_, slot)) = self.find(key) {
-            Some(std::mem::replace(&mut slot.value, value))
-        } else {
-            self.reserve(1);
-            self.insert_inner(key, value);
-            None
+    #[test]
+    fn remove_twice() {
+        let mut hashmap: HashMap<usize, String> =
+            (0..40).map(|key| (key, key.to_string())).collect();
+
+        assert_eq!(hashmap.remove(&7), Some("7".to_string()));
+        assert_eq!(hashmap.remove(&7), None);
+        assert_eq!(hashmap.remove_entry(&7), None);
+        assert_eq!(hashmap.len(), 39);
+
+        for key in 0..40 {
+            hashmap.remove(&key);
+            assert_eq!(hashmap.remove(&key), None);
         }
+
+        assert_eq!(hashmap.len(), 0);
+        assert_eq!(hashmap.remove(&0), None);
+        assert_eq!(hashmap.len(), 0);
     }
 
-    fn insert_inner(&mut self, key: usize, value: V) {
-        let hash = key % self.capacity;
-        let index = self.find_insert_slot(hash);
+    #[test]
+    fn extend_from_slice() {
+        let mut hashmap: HashMap<usize, u64> = (0..10).map(|key| (key, 0)).collect();
+        let pairs: Vec<(usize, u64)> = (5..1000).map(|key| (key, key as u64 * 3)).collect();
 
-        let slot = Slot {
-            flag: TAKEN,
-            key,
-            value,
-        };
+        hashmap.extend_from_slice(&pairs);
 
-        unsafe {
-            self.slots.as_ptr().add(index).write(slot);
-        }
+        assert_eq!(hashmap.len(), 1000);
+        assert_eq!(hashmap[&4], 0);
+        assert_eq!(hashmap[&5], 15);
+        assert_eq!(hashmap[&999], 2997);
+        assert_eq!(hashmap.buckets(), 2048);
+    }
 
-        self.items += 1;
+    #[test]
+    fn map_values() {
+        let mut hashmap: HashMap<usize, f32> =
+            (0..100).map(|key| (key, key as f32 / 2.0)).collect();
+        hashmap.remove(&10);
+        let buckets = hashmap.buckets();
+
+        let strings: HashMap<usize, String> = hashmap.map_values(|value| value.to_string());
+
+        assert_eq!(strings.len(), 99);
+        assert_eq!(strings.buckets(), buckets);
+        assert_eq!(strings.get(&10), None);
+        assert_eq!(strings[&3], "1.5");
+        assert!((0..100)
+            .filter(|&key| key != 10)
+            .all(|key| strings[&key] == (key as f32 / 2.0).to_string()));
+
+        let empty: HashMap<usize, u8> = HashMap::new();
+        assert!(empty.map_values(|value| value as u32).is_empty());
     }
 
-    unsafe fn find_hash_window(&self, hash: usize) -> usize {
-        let mut length = 0;
-        let slots = self.slots.as_ptr();
+    #[test]
+    fn insert_many() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
+        hashmap.insert_many((0..10_000).map(|key| (key, key)));
+
+        // место выделено один раз сразу под все пары
+        assert_eq!(hashmap.buckets(), 16384);
+        assert_eq!(hashmap.len(), 10_000);
+
+        hashmap.insert_many((0..10).map(|key| (key, key + 1)));
+        assert_eq!(hashmap.buckets(), 16384);
+        assert_eq!(hashmap.len(), 10_000);
+        assert_eq!(hashmap[&9], 10);
+    }
 
-        while (&*slots.add(hash + length)).key % self.capacity() == hash {
-            length += 1;
-        }
+    #[test]
+    fn append() {
+        let mut hashmap: HashMap<usize, usize> = (0..100).map(|key| (key, key)).collect();
+        let mut other: HashMap<usize, usize> = (100..200).map(|key| (key, key)).collect();
+        let buckets = other.buckets();
+
+        hashmap.append(&mut other);
+
+        assert_eq!(hashmap.len(), 200);
+        assert!((0..200).all(|key| hashmap[&key] == key));
+        assert!(other.is_empty());
+        assert_eq!(other.buckets(), buckets);
+
+        // значения совпавших ключей берутся из `other`
+        other.extend((150..250).map(|key| (key, key * 10)));
+        hashmap.append(&mut other);
+
+        assert_eq!(hashmap.len(), 250);
+        assert_eq!(hashmap[&149], 149);
+        assert_eq!(hashmap[&150], 1500);
+        assert_eq!(hashmap[&249], 2490);
+        assert!(other.is_empty());
+        assert_eq!(other.buckets(), buckets);
+    }
+
+    #[test]
+    fn index() {
+        let mut hashmap: HashMap<String, usize> = HashMap::new();
+        hashmap.insert("one".to_string(), 1);
 
-        length
+        assert_eq!(hashmap["one"], 1);
     }
 
-    unsafe fn is_window_ready_to_empty(&self, hash: usize, length: usize) -> bool {
-        let mut idx = 0;
-        let mut ready = true;
-        let slots = self.slots.as_ptr();
+    #[test]
+    #[should_panic(expected = "no entry found for key")]
+    fn index_missing() {
+        let hashmap: HashMap<usize, usize> = HashMap::new();
+        let _ = hashmap[&1];
+    }
 
-        while idx < length {
-            let slot = &*slots.add(hash + idx);
+    #[test]
+    fn retain() {
+        let mut hashmap: HashMap<usize, String> = (0..100).map(|i| (i, i.to_string())).collect();
+        hashmap.retain(|key, value| {
+            value.push('!');
+            key % 2 == 0
+        });
 
-            if slot.flag != DELETED {
-                ready = false;
+        assert_eq!(hashmap.len(), 50);
+
+        for key in 0..100 {
+            if key % 2 == 0 {
+                assert_eq!(hashmap.get(&key), Some(&format!("{}!", key)));
+            } else {
+                assert_eq!(hashmap.get(&key), None);
             }
+        }
+
+        hashmap.retain(|_, _| false);
+        assert!(hashmap.is_empty());
+        assert_eq!(hashmap.iter().next(), None);
+    }
+
+    #[test]
+    fn get_or_insert_with() {
+        let mut hashmap: HashMap<usize, String> = HashMap::new();
+        let mut calls = 0;
+
+        for _ in 0..3 {
+            let value = hashmap.get_or_insert_with(1, || {
+                calls += 1;
+                "one".to_string()
+            });
 
-            idx += 1;
+            value.push('!');
         }
 
-        ready
+        assert_eq!(calls, 1);
+        assert_eq!(hashmap.get(&1).map(String::as_str), Some("one!!!"));
     }
 
-    pub fn remove(&mut self, key: usize) -> Option<V> {
-        let value = self.find(key).map(|(idx, slot)| unsafe {
-            let hash = slot.key % self.capacity;
-            let window_length = self.find_hash_window(hash);
+    #[test]
+    fn get_or_try_insert_with() {
+        let mut hashmap: HashMap<usize, String> = HashMap::with_capacity(8);
+        hashmap.insert(1, "one".to_string());
 
-            if window_length == (idx - hash + 1)
-                && self.is_window_ready_to_empty(hash, window_length - 1)
-            {
-                slot.flag = EMPTY;
-            } else {
-                slot.flag = DELETED;
-            }
+        let result = hashmap.get_or_try_insert_with(2, || Err("no value"));
+        assert_eq!(result, Err("no value"));
+        assert_eq!(hashmap.len(), 1);
+        assert!(!hashmap.contains_key(&2));
 
-            std::mem::replace(&mut slot.value, std::mem::zeroed())
-        })?;
+        let value = hashmap.get_or_try_insert_with(2, || Ok::<_, ()>("two".to_string()));
+        assert_eq!(value.map(|value| value.as_str()), Ok("two"));
 
-        self.items -= 1;
+        // для существующего ключа значение не строится
+        let value = hashmap.get_or_try_insert_with(1, || Err(()));
+        assert_eq!(value.map(|value| value.as_str()), Ok("one"));
+        assert_eq!(hashmap.len(), 2);
+    }
 
-        Some(value)
+    #[test]
+    fn iter_sorted() {
+        let mut hashmap: HashMap<usize, usize> =
+            (0..1000).rev().map(|key| (key * 7, key)).collect();
+        hashmap.remove(&700);
+
+        let keys: Vec<usize> = hashmap.iter_sorted().map(|(&key, _)| key).collect();
+
+        assert_eq!(keys.len(), hashmap.len());
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(hashmap.iter_sorted().next(), Some((&0, &0)));
+        assert!(!keys.contains(&700));
     }
 
-    pub fn reserve(&mut self, additional: usize) {
-        if additional + self.items > self.capacity {
-            self.resize(additional + self.items);
+    #[test]
+    fn update() {
+        let mut hashmap: HashMap<usize, String> = HashMap::new();
+        hashmap.insert(1, "one".to_string());
+
+        assert!(hashmap.update(&1, |value| value.push('!')));
+        assert!(!hashmap.update(&2, |_| unreachable!()));
+
+        assert_eq!(hashmap[&1], "one!");
+        assert_eq!(hashmap.get(&2), None);
+        assert_eq!(hashmap.len(), 1);
+    }
+
+    #[test]
+    fn modify_or_insert() {
+        let mut hashmap: HashMap<&str, usize> = HashMap::new();
+        let (mut modified, mut inserted) = (0, 0);
+
+        for word in ["a", "b", "a", "a"] {
+            hashmap.modify_or_insert(
+                word,
+                |count| {
+                    modified += 1;
+                    *count += 1;
+                },
+                || {
+                    inserted += 1;
+                    1
+                },
+            );
         }
+
+        assert_eq!((modified, inserted), (2, 2));
+        assert_eq!(hashmap["a"], 3);
+        assert_eq!(hashmap["b"], 1);
     }
 
-    pub fn resize(&mut self, new_size: usize) {
-        assert!(
-            new_size >= self.items,
-            "the new size is less than count of items"
-        );
+    #[test]
+    fn shrink_to_fit() {
+        let mut hashmap: HashMap<usize, usize> = HashMap::new();
 
-        unsafe {
-            let mut map = Self::new_inner(new_size);
-            let slots = self.slots.as_ptr();
+        for key in 0..1_000_000 {
+            hashmap.insert(key, key);
+        }
 
-            for idx in 0..self.capacity {
-                let mut slot = &mut *slots.add(idx);
+        for key in 100..1_000_000 {
+            hashmap.remove(&key);
+        }
 
-                if slot.flag == TAKEN {
-                    let hash = slot.key % map.capacity();
-                    let index = map.find_insert_slot(hash);
-                    std::mem::swap(&mut *map.slots.as_ptr().add(index), &mut slot);
-                    map.items += 1;
-                }
-            }
+        let capacity = hashmap.capacity();
+        hashmap.shrink_to_fit();
 
-            std::mem::swap(self, &mut map);
+        assert!(hashmap.capacity() * 1000 < capacity);
+        assert_eq!(hashmap.buckets(), 128);
+        assert_eq!(hashmap.len(), 100);
+
+        for key in 0..100 {
+            assert_eq!(hashmap.get(&key).copied(), Some(key));
         }
+
+        // таблица уже минимальная, и память не перевыделяется
+        let slots = hashmap.slots;
+        hashmap.shrink_to_fit();
+        assert_eq!(hashmap.buckets(), 128);
+        assert_eq!(hashmap.slots, slots);
+
+        // после `reserve_exact` слотов меньше, чем дало бы округление до степени двойки
+        let mut exact: HashMap<usize, usize> = HashMap::new();
+        exact.reserve_exact(100);
+        exact.extend((0..100).map(|key| (key, key)));
+        let slots = exact.slots;
+        exact.shrink_to_fit();
+        assert_eq!(exact.buckets(), 115);
+        assert_eq!(exact.slots, slots);
+
+        hashmap.clear();
+        hashmap.shrink_to_fit();
+        assert_eq!(hashmap.capacity(), 0);
+
+        hashmap.insert(1, 1);
+        assert_eq!(hashmap.get(&1).copied(), Some(1));
     }
 
-    pub fn capacity(&self) -> usize {
-        self.capacity
+    #[test]
+    fn shrink_to() {
+        let mut hashmap: HashMap<usize, usize> = (0..10_000).map(|key| (key, key)).collect();
+        hashmap.retain(|&key, _| key < 10);
+
+        hashmap.shrink_to(1000);
+        assert!(hashmap.capacity() >= 1000);
+        assert_eq!(hashmap.buckets(), 2048);
+
+        // таблица уже меньше, чем просят
+        hashmap.shrink_to(5000);
+        assert_eq!(hashmap.buckets(), 2048);
+
+        // элементов больше, чем `min_capacity`
+        hashmap.shrink_to(2);
+        assert_eq!(hashmap.buckets(), 16);
+        assert!((0..10).all(|key| hashmap[&key] == key));
     }
 
-    pub fn len(&self) -> usize {
-        self.items
+    #[test]
+    fn identity_hasher_mixing() {
+        let mut hashmap: HashMap<usize, usize, IdentityState> =
+            HashMap::with_capacity_and_hasher(896, IdentityState::default());
+        let capacity = hashmap.buckets();
+        let keys: Vec<usize> = (0..64).map(|i| i * capacity).collect();
+
+        for &key in &keys {
+            hashmap.insert(key, key);
+        }
+
+        let naive: std::collections::HashSet<usize> =
+            keys.iter().map(|key| key % capacity).collect();
+        let mixed: std::collections::HashSet<usize> = keys
+            .iter()
+            .map(|key| make_hash(hashmap.hasher(), key) % capacity)
+            .collect();
+
+        assert_eq!(capacity, 1024);
+        assert_eq!(naive.len(), 1);
+        assert!(mixed.len() > 48, "{}", mixed.len());
+        assert!(keys.iter().all(|key| hashmap[key] == *key));
     }
-}
 
-impl<V> Drop for HashMap<V> {
-    fn drop(&mut self) {
-        if self.capacity != 0 {
-            unsafe {
-                let layout = Layout::array::<Slot<V>>(self.capacity).unwrap();
-                let slots = self.slots.as_ptr();
-
-                if std::mem::needs_drop::<V>() {
-                    for idx in 0..self.capacity {
-                        let slot = &*slots.add(idx);
-                        if slot.flag == TAKEN {
-                            slots.add(idx).drop_in_place();
-                        }
-                    }
-                }
+    #[test]
+    fn custom_hasher() {
+        let mut hashmap: HashMap<usize, usize, FnvState> =
+            HashMap::with_capacity_and_hasher(56, FnvState::default());
+        let keys: Vec<usize> = (0..32).map(|i| i * 64).collect();
 
-                dealloc(slots as *mut u8, layout);
-            }
+        for &key in &keys {
+            hashmap.insert(key, key);
         }
-    }
-}
 
-unsafe impl<V: Send> Send for HashMap<V> {}
+        assert_eq!(hashmap.buckets(), 64);
 
-#[cfg(test)]
-mod tests {
-    extern crate test;
+        let naive: std::collections::HashSet<usize> = keys.iter().map(|key| key % 64).collect();
+        let hashed: std::collections::HashSet<usize> = keys
+            .iter()
+            .map(|key| make_hash(hashmap.hasher(), key) % 64)
+            .collect();
 
-    use super::HashMap;
-    use rand::random;
-    use std::collections::HashMap as StdMap;
-    use test::Bencher;
+        // при KEY % MAP_CAPACITY все ключи попадают в один слот
+        assert_eq!(naive.len(), 1);
+        assert!(hashed.len() > 16);
 
-    #[test]
-    fn empty_hashmap() {
-        let hashmap: HashMap<f32> = HashMap::new();
-        assert_eq!(hashmap.capacity(), 0);
-        assert_eq!(hashmap.get(0), None);
-    }
+        let mut other: HashMap<usize, usize, FnvState> = HashMap::default();
+        other.extend(&hashmap);
 
-    #[test]
-    fn resize() {
-        let mut hashmap: HashMap<f32> = HashMap::with_capacity(1);
-        hashmap.insert(0, 0.1);
-        hashmap.insert(1, 0.2);
-        assert_eq!(hashmap.get(0).copied(), Some(0.1));
-        assert_eq!(hashmap.get(1).copied(), Some(0.2));
+        // одинаковый детерминированный хэшер дает одинаковое расположение
+        assert_eq!(
+            make_hash(hashmap.hasher(), &64),
+            make_hash(other.hasher(), &64)
+        );
+        assert_eq!(hashmap, other);
     }
 
     #[test]
-    fn capacity() {
-        let mut hashmap: HashMap<f32> = HashMap::with_capacity(12);
-        assert_eq!(hashmap.capacity(), 16);
-        hashmap.insert(15, 0.21);
-        assert_eq!(hashmap.capacity(), 16);
+    fn shared_between_threads() {
+        let hashmap: HashMap<usize, String> = (0..100).map(|i| (i, i.to_string())).collect();
+
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let hashmap = &hashmap;
+
+                scope.spawn(move || {
+                    for key in (thread..100).step_by(4) {
+                        assert_eq!(hashmap.get(&key), Some(&key.to_string()));
+                    }
+
+                    assert_eq!(hashmap.iter().count(), 100);
+                    assert!(hashmap.iter().all(|(key, value)| key.to_string() == *value));
+                });
+            }
+
+            // итератор, начатый в одном потоке, можно дообойти в другом
+            let mut iter = hashmap.keys();
+            let first = *iter.next().unwrap();
+
+            scope.spawn(move || {
+                let mut keys: Vec<usize> = iter.copied().collect();
+                keys.push(first);
+                keys.sort_unstable();
+                assert_eq!(keys, (0..100).collect::<Vec<_>>());
+            });
+        });
     }
 
     #[test]
-    fn collision() {
-        let mut hashmap: HashMap<f32> = HashMap::with_capacity(2);
-        hashmap.insert(2, 0.1); // 2 % 2 == 0
-        hashmap.insert(4, 0.2); // 4 % 2 == 0
-
-        assert_eq!(hashmap.get(2).copied(), Some(0.1));
-        assert_eq!(hashmap.get(4).copied(), Some(0.2));
+    fn string_keys() {
+        let mut hashmap: HashMap<String, usize> = HashMap::new();
+        hashmap.insert("one".to_string(), 1);
+        hashmap.insert("two".to_string(), 2);
+
+        assert_eq!(hashmap.get("one").copied(), Some(1));
+        assert_eq!(hashmap.insert("two".to_string(), 22), Some(2));
+        assert_eq!(hashmap.remove("one"), Some(1));
+        assert_eq!(hashmap.get("one"), None);
+        assert_eq!(hashmap.get("two").copied(), Some(22));
+        assert_eq!(hashmap.get("three"), None);
+        assert_eq!(hashmap.len(), 1);
     }
 
     #[test]
-    fn collision_remove() {
-        let mut hashmap: HashMap<f32> = HashMap::with_capacity(4);
-        hashmap.insert(4, 0.1); // 4 % 4 == 0
-        hashmap.insert(8, 0.2); // 8 % 4 == 0
-        hashmap.insert(12, 0.3); // 12 % 4 == 0
+    fn tuple_keys() {
+        let mut hashmap: HashMap<(u32, u32), f32> = HashMap::new();
 
-        hashmap.remove(8);
+        for x in 0..10 {
+            for y in 0..10 {
+                hashmap.insert((x, y), (x * y) as f32);
+            }
+        }
 
-        assert_eq!(hashmap.get(4).copied(), Some(0.1));
-        assert_eq!(hashmap.get(12).copied(), Some(0.3));
+        assert_eq!(hashmap.len(), 100);
+        assert_eq!(hashmap.get(&(3, 7)).copied(), Some(21.0));
+        assert_eq!(hashmap.remove(&(3, 7)), Some(21.0));
+        assert_eq!(hashmap.get(&(3, 7)), None);
+        assert_eq!(hashmap.get(&(7, 3)).copied(), Some(21.0));
     }
 
     #[test]
     fn double_insert() {
-        let mut hashmap: HashMap<f32> = HashMap::new();
+        let mut hashmap: HashMap<usize, f32> = HashMap::new();
         hashmap.insert(10, 0.1);
         assert_eq!(hashmap.insert(10, 0.2), Some(0.1));
-        assert_eq!(hashmap.get(10).copied(), Some(0.2));
+        assert_eq!(hashmap.get(&10).copied(), Some(0.2));
         assert_eq!(hashmap.len(), 1);
     }
 
     #[test]
     fn dont_die_please() {
-        let mut hashmap: HashMap<f32> = HashMap::new();
+        let mut hashmap: HashMap<usize, f32> = HashMap::new();
         let mut array = vec![];
 
         for key in 0..1_000_000 {
@@ -323,18 +3493,60 @@ mod tests {
         }
 
         for (key, value) in array.iter().enumerate() {
-            assert_eq!(hashmap.get(key), Some(value));
-            assert_eq!(hashmap.remove(key), Some(*value));
+            assert_eq!(hashmap.get(&key), Some(value));
+            assert_eq!(hashmap.remove(&key), Some(*value));
         }
 
         for key in 0..1_000_000 {
-            assert_eq!(hashmap.get(key), None);
+            assert_eq!(hashmap.get(&key), None);
+        }
+    }
+
+    // то же с владеющими кучей значениями: значение, потерянное или скопированное
+    // при росте или удалении, дало бы утечку или двойное освобождение
+    #[test]
+    fn dont_die_please_strings() {
+        // под miri миллион вставок шел бы часами, а тысячи хватает на несколько ростов,
+        // и тест проходит примерно за полминуты
+        let count = if cfg!(miri) { 1000 } else { 1_000_000 };
+        let mut hashmap: HashMap<usize, (String, Tracked)> = HashMap::new();
+
+        for key in 0..count {
+            hashmap.insert(key, (key.to_string(), Tracked::new(key)));
+        }
+
+        assert_eq!(live(), count as isize);
+
+        for key in 0..count {
+            let (string, tracked) = hashmap.remove(&key).unwrap();
+            assert_eq!(string, key.to_string());
+            assert_eq!(tracked.0, key);
+        }
+
+        assert_eq!(live(), 0);
+        assert!(hashmap.is_empty());
+
+        for key in 0..count {
+            assert_eq!(hashmap.get(&key).map(|(string, _)| string), None);
         }
     }
 
     #[bench]
     fn my_hashmap(b: &mut Bencher) {
-        let mut hashmap: HashMap<u64> = HashMap::new();
+        let mut hashmap: HashMap<usize, u64> = HashMap::new();
+
+        b.iter(|| {
+            for key in 0..500_000 {
+                let value = random();
+                hashmap.insert(key, value);
+            }
+        });
+    }
+
+    #[bench]
+    fn my_hashmap_fx(b: &mut Bencher) {
+        let mut hashmap: HashMap<usize, u64, FxBuildHasher> =
+            HashMap::with_hasher(FxBuildHasher::default());
 
         b.iter(|| {
             for key in 0..500_000 {
@@ -344,6 +3556,125 @@ mod tests {
         });
     }
 
+    // 80% занятых слотов: половина поисков попадает, половина промахивается
+    #[bench]
+    fn my_hashmap_lookup(b: &mut Bencher) {
+        let mut hashmap: HashMap<usize, u64> = HashMap::with_capacity((1 << 16) * 7 / 8);
+        let items = (1 << 16) * 4 / 5;
+
+        for key in 0..items {
+            hashmap.insert(key, key as u64);
+        }
+
+        assert_eq!(hashmap.buckets(), 1 << 16);
+
+        b.iter(|| {
+            let mut found = 0;
+
+            for key in (0..items * 2).step_by(2) {
+                found += hashmap.contains_key(&key) as usize;
+            }
+
+            found
+        });
+    }
+
+    // таблица на 16 МБ не помещается в L2, и почти каждый поиск промахивается мимо кэша
+    #[bench]
+    fn my_hashmap_lookup_large(b: &mut Bencher) {
+        let mut hashmap: HashMap<usize, u64> = HashMap::with_capacity((1 << 20) * 7 / 8);
+        let items = (1 << 20) * 4 / 5;
+
+        for key in 0..items {
+            hashmap.insert(key, key as u64);
+        }
+
+        assert_eq!(hashmap.buckets(), 1 << 20);
+
+        b.iter(|| {
+            let mut found = 0;
+
+            for key in (0..items * 2).step_by(2) {
+                found += hashmap.contains_key(&key) as usize;
+            }
+
+            found
+        });
+    }
+
+    // ключи отличаются только в конце, и полное сравнение дорого, но до него
+    // доходит почти только искомый ключ, остальные отсекаются по фрагменту хэша
+    #[bench]
+    fn my_hashmap_lookup_strings(b: &mut Bencher) {
+        let prefix = "x".repeat(64);
+        let keys: Vec<String> = (0..50_000)
+            .map(|key| format!("{}{}", prefix, key))
+            .collect();
+        let hashmap: HashMap<&str, usize> = keys.iter().map(|key| (key.as_str(), 0)).collect();
+
+        b.iter(|| {
+            keys.iter()
+                .filter(|key| hashmap.contains_key(key.as_str()))
+                .count()
+        });
+    }
+
+    // значения не требуют drop, поэтому `clear` только заполняет флаги одним
+    // `write_bytes` и не зависит от количества элементов
+    #[bench]
+    fn my_hashmap_clear(b: &mut Bencher) {
+        let mut hashmap: HashMap<usize, u64> = HashMap::with_capacity(1_000_000);
+
+        for key in 0..1_000_000 {
+            hashmap.insert(key, key as u64);
+        }
+
+        b.iter(|| {
+            hashmap.clear();
+            hashmap.len()
+        });
+    }
+
+    // каждый рост удваивает таблицу, поэтому 1M вставок перевыделяют память 18 раз
+    #[bench]
+    fn my_hashmap_grow(b: &mut Bencher) {
+        b.iter(|| {
+            let mut hashmap: HashMap<usize, u64> = HashMap::new();
+
+            for key in 0..1_000_000 {
+                hashmap.insert(key, key as u64);
+            }
+
+            hashmap
+        });
+    }
+
+    fn bench_pairs() -> Vec<(usize, u64)> {
+        (0..100_000).map(|key| (key, key as u64)).collect()
+    }
+
+    #[bench]
+    fn my_hashmap_extend(b: &mut Bencher) {
+        let pairs = bench_pairs();
+
+        b.iter(|| {
+            let mut hashmap: HashMap<usize, u64> = HashMap::new();
+            hashmap.extend(pairs.iter().copied());
+            hashmap
+        });
+    }
+
+    #[bench]
+    fn my_hashmap_extend_from_slice(b: &mut Bencher) {
+        let pairs = bench_pairs();
+
+        b.iter(|| {
+            let mut hashmap: HashMap<usize, u64> = HashMap::new();
+            hashmap.extend_from_slice(&pairs);
+            hashmap
+        });
+    }
+
     #[bench]
     fn std_hashmap(b: &mut Bencher) {
         let mut stdmap: StdMap<usize, u64> = StdMap::new();
@@ -0,0 +1,248 @@
+// сравнение стратегий пробирования на одних и тех же ключах и заполненности.
+// чтобы добавить стратегию, достаточно реализовать для нее `Strategy` и дописать
+// ее в `probing_report`. отчет собирается с оптимизациями:
+//
+//     cargo test --release probing_report -- --ignored --nocapture
+//
+// треугольное пробирование заменяется линейным с фичей `linear-probing`
+
+extern crate test;
+
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use test::black_box;
+
+use crate::{HashMap, RobinHoodMap};
+
+// наибольшая доля, которую разрешает `HashMap::set_max_load_factor`
+const MAX_LOAD_FACTOR: f64 = 0.95;
+
+const LOADS: [f64; 3] = [0.5, 0.75, 0.9];
+
+trait Strategy: Sized {
+    fn name() -> &'static str;
+
+    // таблица примерно на `buckets` слотов, в которую поместится доля `load` от них.
+    // `None`, если стратегия не допускает такой заполненности
+    fn with_load(buckets: usize, load: f64) -> Option<Self>;
+
+    fn insert(&mut self, key: usize);
+
+    fn contains(&self, key: usize) -> bool;
+
+    fn remove(&mut self, key: usize) -> bool;
+}
+
+// размер таблицы - степень двойки, поэтому шаг пробирования растет
+struct Triangular(HashMap<usize, usize>);
+
+impl Strategy for Triangular {
+    fn name() -> &'static str {
+        if cfg!(feature = "linear-probing") {
+            "linear"
+        } else {
+            "triangular"
+        }
+    }
+
+    fn with_load(buckets: usize, _load: f64) -> Option<Self> {
+        let mut map = HashMap::with_capacity(buckets * 7 / 8);
+        map.set_max_load_factor(MAX_LOAD_FACTOR);
+        assert_eq!(map.buckets(), buckets);
+        Some(Triangular(map))
+    }
+
+    fn insert(&mut self, key: usize) {
+        self.0.insert(key, key);
+    }
+
+    fn contains(&self, key: usize) -> bool {
+        self.0.contains_key(&key)
+    }
+
+    fn remove(&mut self, key: usize) -> bool {
+        self.0.remove(&key).is_some()
+    }
+}
+
+// на таблице после `reserve_exact` размер не степень двойки, и пробирование всегда линейное
+struct Linear(HashMap<usize, usize>);
+
+impl Strategy for Linear {
+    fn name() -> &'static str {
+        "linear"
+    }
+
+    fn with_load(buckets: usize, _load: f64) -> Option<Self> {
+        let mut map = HashMap::new();
+        map.set_max_load_factor(MAX_LOAD_FACTOR);
+        map.reserve_exact(((buckets - 1) as f64 * MAX_LOAD_FACTOR) as usize);
+        assert!(!map.buckets().is_power_of_two());
+        Some(Linear(map))
+    }
+
+    fn insert(&mut self, key: usize) {
+        self.0.insert(key, key);
+    }
+
+    fn contains(&self, key: usize) -> bool {
+        self.0.contains_key(&key)
+    }
+
+    fn remove(&mut self, key: usize) -> bool {
+        self.0.remove(&key).is_some()
+    }
+}
+
+struct RobinHood(RobinHoodMap<usize, usize>);
+
+impl Strategy for RobinHood {
+    fn name() -> &'static str {
+        "robin hood"
+    }
+
+    // доля заполнения у `RobinHoodMap` не настраивается
+    fn with_load(buckets: usize, load: f64) -> Option<Self> {
+        let map = RobinHoodMap::with_capacity((buckets as f64 * load) as usize);
+        (map.buckets() == buckets).then_some(RobinHood(map))
+    }
+
+    fn insert(&mut self, key: usize) {
+        self.0.insert(key, key);
+    }
+
+    fn contains(&self, key: usize) -> bool {
+        self.0.contains_key(&key)
+    }
+
+    fn remove(&mut self, key: usize) -> bool {
+        self.0.remove(&key).is_some()
+    }
+}
+
+// наносекунд на операцию
+struct Throughput {
+    insert: f64,
+    hit: f64,
+    miss: f64,
+    remove: f64,
+}
+
+// заполняет таблицу ключами `keys` до доли `load`, ищет каждый вставленный
+// и столько же отсутствующих, затем удаляет все вставленные
+fn measure<M: Strategy>(buckets: usize, load: f64, keys: &[usize]) -> Option<Throughput> {
+    let mut map = M::with_load(buckets, load)?;
+    let items = (buckets as f64 * load) as usize;
+    let (present, absent) = keys.split_at(items);
+    let absent = &absent[..items];
+
+    let per_op = |start: Instant| start.elapsed().as_nanos() as f64 / items as f64;
+
+    let start = Instant::now();
+    for &key in present {
+        map.insert(key);
+    }
+    let insert = per_op(start);
+
+    let start = Instant::now();
+    assert!(present.iter().all(|&key| black_box(map.contains(key))));
+    let hit = per_op(start);
+
+    let start = Instant::now();
+    assert!(absent.iter().all(|&key| !black_box(map.contains(key))));
+    let miss = per_op(start);
+
+    let start = Instant::now();
+    assert!(present.iter().all(|&key| map.remove(key)));
+    let remove = per_op(start);
+
+    Some(Throughput {
+        insert,
+        hit,
+        miss,
+        remove,
+    })
+}
+
+// различающиеся ключи: первые вставляются, остальные ищутся как отсутствующие
+fn key_sets(count: usize) -> [(&'static str, Vec<usize>); 2] {
+    let mut rng = StdRng::seed_from_u64(97);
+    let mut random: Vec<usize> = (0..count * 2).map(|_| rng.gen()).collect();
+    random.sort_unstable();
+    random.dedup();
+
+    // перемешиваем обратно, чтобы порядок вставки не был возрастающим
+    for idx in (1..random.len()).rev() {
+        random.swap(idx, rng.gen_range(0, idx + 1));
+    }
+
+    random.truncate(count);
+
+    [("sequential", (0..count).collect()), ("random", random)]
+}
+
+fn report<M: Strategy>(buckets: usize, loads: &[f64]) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for (keys_name, keys) in key_sets(buckets * 2) {
+        for &load in loads {
+            let line = match measure::<M>(buckets, load, &keys) {
+                Some(t) => format!(
+                    "{:<12}{:<12}{:>4.0}%{:>10.1}{:>10.1}{:>10.1}{:>10.1}",
+                    M::name(),
+                    keys_name,
+                    load * 100.0,
+                    t.insert,
+                    t.hit,
+                    t.miss,
+                    t.remove,
+                ),
+                None => format!(
+                    "{:<12}{:<12}{:>4.0}%  unsupported",
+                    M::name(),
+                    keys_name,
+                    load * 100.0
+                ),
+            };
+
+            lines.push(line);
+        }
+    }
+
+    lines
+}
+
+#[test]
+#[ignore]
+fn probing_report() {
+    let buckets = 1 << 20;
+
+    println!(
+        "{:<12}{:<12}{:>5}{:>10}{:>10}{:>10}{:>10}",
+        "strategy", "keys", "load", "insert", "hit", "miss", "remove"
+    );
+
+    for line in report::<Triangular>(buckets, &LOADS)
+        .into_iter()
+        .chain(report::<Linear>(buckets, &LOADS))
+        .chain(report::<RobinHood>(buckets, &LOADS))
+    {
+        println!("{}", line);
+    }
+}
+
+#[test]
+fn harness_runs() {
+    for lines in [
+        report::<Triangular>(64, &LOADS),
+        report::<Linear>(64, &LOADS),
+        report::<RobinHood>(64, &LOADS),
+    ] {
+        assert_eq!(lines.len(), 6);
+    }
+
+    // 90% больше предела `RobinHoodMap` в 7/8 слотов
+    assert!(measure::<RobinHood>(64, 0.9, &(0..128).collect::<Vec<_>>()).is_none());
+}
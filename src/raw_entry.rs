@@ -0,0 +1,292 @@
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash};
+
+use crate::{mix_hash, DefaultHashBuilder, HashMap};
+
+/// поиск по заранее посчитанному хэшу без изменения таблицы
+pub struct RawEntryBuilder<'a, K, V, S = DefaultHashBuilder> {
+    pub(crate) map: &'a HashMap<K, V, S>,
+}
+
+/// поиск по заранее посчитанному хэшу, возвращающий слот для изменения или вставки
+pub struct RawEntryBuilderMut<'a, K, V, S = DefaultHashBuilder> {
+    pub(crate) map: &'a mut HashMap<K, V, S>,
+}
+
+/// слот таблицы, найденный через `raw_entry_mut`
+pub enum RawEntryMut<'a, K, V, S = DefaultHashBuilder> {
+    Occupied(RawOccupiedEntryMut<'a, K, V, S>),
+    Vacant(RawVacantEntryMut<'a, K, V, S>),
+}
+
+/// слот, в котором уже лежит подходящий ключ
+pub struct RawOccupiedEntryMut<'a, K, V, S = DefaultHashBuilder> {
+    map: &'a mut HashMap<K, V, S>,
+    index: usize,
+}
+
+/// свободный слот, в который можно вставить ключ с тем же хэшем
+pub struct RawVacantEntryMut<'a, K, V, S = DefaultHashBuilder> {
+    map: &'a mut HashMap<K, V, S>,
+    hash: usize,
+    index: usize,
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> RawEntryBuilder<'a, K, V, S> {
+    /// `is_match` вызывается только для ключей, у которых совпал фрагмент хэша
+    pub fn from_hash<F: FnMut(&K) -> bool>(self, hash: u64, is_match: F) -> Option<(&'a K, &'a V)> {
+        let map = self.map;
+        let (idx, _) = map.find_by(mix_hash(hash), is_match)?;
+        let slot = unsafe { map.slot(idx) };
+
+        Some((&slot.key, &slot.value))
+    }
+
+    pub fn from_key_hashed_nocheck<Q>(self, hash: u64, key: &Q) -> Option<(&'a K, &'a V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq,
+    {
+        self.from_hash(hash, |stored| stored.borrow() == key)
+    }
+
+    pub fn from_key<Q>(self, key: &Q) -> Option<(&'a K, &'a V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = self.map.hasher().hash_one(key);
+        self.from_key_hashed_nocheck(hash, key)
+    }
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> RawEntryBuilderMut<'a, K, V, S> {
+    pub fn from_hash<F: FnMut(&K) -> bool>(
+        self,
+        hash: u64,
+        is_match: F,
+    ) -> RawEntryMut<'a, K, V, S> {
+        let map = self.map;
+        let hash = mix_hash(hash);
+
+        // как и в `entry`, таблица растет только при вставке в `RawVacantEntryMut`
+        let found = if map.capacity == 0 {
+            Err(0)
+        } else {
+            map.find_or_find_insert_slot(hash, is_match)
+        };
+
+        match found {
+            Ok(index) => RawEntryMut::Occupied(RawOccupiedEntryMut { map, index }),
+            Err(index) => RawEntryMut::Vacant(RawVacantEntryMut { map, hash, index }),
+        }
+    }
+
+    pub fn from_key_hashed_nocheck<Q>(self, hash: u64, key: &Q) -> RawEntryMut<'a, K, V, S>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq,
+    {
+        self.from_hash(hash, |stored| stored.borrow() == key)
+    }
+
+    pub fn from_key<Q>(self, key: &Q) -> RawEntryMut<'a, K, V, S>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = self.map.hasher().hash_one(key);
+        self.from_key_hashed_nocheck(hash, key)
+    }
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> RawEntryMut<'a, K, V, S> {
+    /// вставляет `default()` при отсутствии ключа, ключ должен иметь тот же хэш,
+    /// что был передан при поиске
+    pub fn or_insert_with<F: FnOnce() -> (K, V)>(self, default: F) -> (&'a mut K, &'a mut V) {
+        match self {
+            RawEntryMut::Occupied(entry) => entry.into_key_value(),
+            RawEntryMut::Vacant(entry) => {
+                let (key, value) = default();
+                entry.insert(key, value)
+            }
+        }
+    }
+}
+
+impl<'a, K, V, S> RawOccupiedEntryMut<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        unsafe { &self.map.slot(self.index).key }
+    }
+
+    pub fn get(&self) -> &V {
+        unsafe { &self.map.slot(self.index).value }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { &mut self.map.slot_mut(self.index).value }
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { &mut self.map.slot_mut(self.index).value }
+    }
+
+    /// ключ можно менять только так, чтобы не изменились ни хэш, ни равенство
+    pub fn into_key_value(self) -> (&'a mut K, &'a mut V) {
+        let slot = unsafe { self.map.slot_mut(self.index) };
+        (&mut slot.key, &mut slot.value)
+    }
+
+    /// заменяет значение, возвращая старое
+    pub fn insert(&mut self, value: V) -> V {
+        core::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        let slot = unsafe { self.map.take(self.index) };
+        (slot.key, slot.value)
+    }
+}
+
+impl<'a, K, V, S> RawVacantEntryMut<'a, K, V, S> {
+    /// ключ должен иметь тот же хэш, что был передан при поиске
+    pub fn insert(self, key: K, value: V) -> (&'a mut K, &'a mut V)
+    where
+        K: Hash + Eq,
+        S: BuildHasher,
+    {
+        let map = self.map;
+        let mut index = self.index;
+
+        if map.items + 1 + map.tombstones > map.capacity() {
+            map.reserve(1);
+            index = map.find_insert_slot(self.hash);
+        }
+
+        unsafe {
+            map.insert_in_slot(index, self.hash, key, value);
+            let slot = map.slot_mut(index);
+            (&mut slot.key, &mut slot.value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::BuildHasher;
+
+    use crate::{HashMap, RawEntryMut};
+
+    // считает, сколько раз таблица хэшировала ключ
+    #[derive(Default)]
+    struct CountingState {
+        calls: Cell<usize>,
+    }
+
+    impl BuildHasher for CountingState {
+        type Hasher = DefaultHasher;
+
+        fn build_hasher(&self) -> DefaultHasher {
+            self.calls.set(self.calls.get() + 1);
+            DefaultHasher::new()
+        }
+    }
+
+    #[test]
+    fn hash_once() {
+        let mut map: HashMap<String, usize, CountingState> =
+            HashMap::with_capacity_and_hasher(16, CountingState::default());
+        map.insert("one".to_string(), 1);
+
+        let key = "two";
+        let calls = map.hasher().calls.get();
+        let hash = map.hasher().hash_one(key);
+
+        assert!(map
+            .raw_entry()
+            .from_hash(hash, |stored| stored == key)
+            .is_none());
+
+        match map.raw_entry_mut().from_key_hashed_nocheck(hash, key) {
+            RawEntryMut::Occupied(_) => unreachable!(),
+            RawEntryMut::Vacant(entry) => {
+                let (_, value) = entry.insert(key.to_string(), 2);
+                *value += 10;
+            }
+        }
+
+        assert_eq!(map.hasher().calls.get(), calls + 1);
+        assert_eq!(map.raw_entry().from_key(key), Some((&key.to_string(), &12)));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn occupied() {
+        let mut map: HashMap<usize, &str> = HashMap::new();
+        map.insert(1, "one");
+        let hash = map.hasher().hash_one(1usize);
+
+        match map.raw_entry_mut().from_hash(hash, |&key| key == 1) {
+            RawEntryMut::Occupied(mut entry) => {
+                assert_eq!(entry.insert("uno"), "one");
+                assert_eq!(entry.remove_entry(), (1, "uno"));
+            }
+            RawEntryMut::Vacant(_) => unreachable!(),
+        }
+
+        assert!(map.is_empty());
+
+        let (_, value) = map
+            .raw_entry_mut()
+            .from_key(&2)
+            .or_insert_with(|| (2, "two"));
+        assert_eq!(*value, "two");
+        assert_eq!(map.get(&2), Some(&"two"));
+    }
+
+    #[test]
+    fn lookup_keeps_full_table() {
+        let mut map: HashMap<usize, usize> = HashMap::with_capacity(14);
+        map.extend((0..map.capacity()).map(|key| (key, key)));
+
+        let len = map.len();
+        assert_eq!(map.buckets(), 16);
+        assert_eq!(len, map.capacity());
+
+        assert!(matches!(
+            map.raw_entry_mut().from_key(&len),
+            RawEntryMut::Vacant(_)
+        ));
+
+        match map.raw_entry_mut().from_key(&0) {
+            RawEntryMut::Occupied(entry) => assert_eq!(entry.remove_entry(), (0, 0)),
+            RawEntryMut::Vacant(_) => unreachable!(),
+        }
+
+        assert_eq!(map.buckets(), 16);
+
+        // на место удаленного ключа вставка тоже обходится без роста
+        map.raw_entry_mut().from_key(&0).or_insert_with(|| (0, 0));
+        assert_eq!(map.buckets(), 16);
+
+        map.raw_entry_mut()
+            .from_key(&len)
+            .or_insert_with(|| (len, len));
+        assert_eq!(map.buckets(), 32);
+        assert!((0..=len).all(|key| map[&key] == key));
+    }
+
+    #[test]
+    fn insert_into_empty() {
+        let mut map: HashMap<usize, usize> = HashMap::new();
+
+        match map.raw_entry_mut().from_key(&1) {
+            RawEntryMut::Occupied(_) => unreachable!(),
+            RawEntryMut::Vacant(entry) => assert_eq!(entry.insert(1, 2), (&mut 1, &mut 2)),
+        }
+
+        assert_eq!(map[&1], 2);
+    }
+}
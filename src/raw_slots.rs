@@ -0,0 +1,100 @@
+use core::mem::MaybeUninit;
+
+use crate::{is_full, Slot};
+
+/// слот таблицы как он лежит в памяти. занят он или нет, известно только по флагу
+/// с тем же индексом, в незанятом слоте байты не инициализированы или остались
+/// от перемещенного значения
+#[repr(transparent)]
+pub struct RawSlot<K, V>(MaybeUninit<Slot<K, V>>);
+
+impl<K, V> RawSlot<K, V> {
+    /// # Safety
+    ///
+    /// слот должен быть занят
+    pub unsafe fn key(&self) -> &K {
+        &(*self.0.as_ptr()).key
+    }
+
+    /// # Safety
+    ///
+    /// слот должен быть занят
+    pub unsafe fn value(&self) -> &V {
+        &(*self.0.as_ptr()).value
+    }
+}
+
+/// массивы флагов и слотов таблицы для просмотра без итератора. флаг занятого слота
+/// имеет выставленный старший бит, у пустых и удаленных он сброшен
+pub struct RawSlots<'a, K, V> {
+    pub(crate) flags: &'a [u8],
+    pub(crate) slots: &'a [RawSlot<K, V>],
+}
+
+impl<'a, K, V> RawSlots<'a, K, V> {
+    /// по флагу на каждый слот
+    pub fn flags(&self) -> &'a [u8] {
+        self.flags
+    }
+
+    pub fn slots(&self) -> &'a [RawSlot<K, V>] {
+        self.slots
+    }
+
+    pub fn is_full(&self, index: usize) -> bool {
+        is_full(self.flags[index])
+    }
+
+    /// ключ и значение занятого слота
+    pub fn get(&self, index: usize) -> Option<(&'a K, &'a V)> {
+        if !self.is_full(index) {
+            return None;
+        }
+
+        let slot = &self.slots[index];
+        unsafe { Some((slot.key(), slot.value())) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::HashMap;
+
+    #[test]
+    fn raw_slots() {
+        let mut hashmap: HashMap<usize, usize> = (0..100).map(|key| (key, key * 2)).collect();
+
+        for key in (0..100).step_by(3) {
+            hashmap.remove(&key);
+        }
+
+        let raw = hashmap.raw_slots();
+        assert_eq!(raw.flags().len(), hashmap.buckets());
+        assert_eq!(raw.slots().len(), hashmap.buckets());
+
+        let full = raw.flags().iter().filter(|&&flag| flag & 0x80 != 0).count();
+        assert_eq!(full, hashmap.len());
+
+        let mut sum = 0;
+
+        for (idx, slot) in raw.slots().iter().enumerate() {
+            if raw.is_full(idx) {
+                sum += unsafe { slot.value() };
+                assert_eq!(raw.get(idx), unsafe { Some((slot.key(), slot.value())) });
+            } else {
+                assert_eq!(raw.get(idx), None);
+            }
+        }
+
+        assert_eq!(sum, hashmap.values().sum::<usize>());
+    }
+
+    #[test]
+    fn raw_slots_empty() {
+        let hashmap: HashMap<usize, usize> = HashMap::new();
+        let raw = hashmap.raw_slots();
+
+        assert!(raw.flags().is_empty());
+        assert!(raw.slots().is_empty());
+    }
+}
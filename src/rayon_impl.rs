@@ -0,0 +1,90 @@
+use core::marker::PhantomData;
+
+use rayon::iter::plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::iter::RawIter;
+use crate::{HashMap, Iter};
+
+/// параллельный итератор по парам ключ-значение: массив слотов делится на части,
+/// каждая из которых обходится отдельно, пропуская пустые и удаленные слоты
+pub struct ParIter<'a, K, V> {
+    inner: RawIter<K, V>,
+    marker: PhantomData<&'a (K, V)>,
+}
+
+struct ParIterProducer<'a, K, V> {
+    inner: RawIter<K, V>,
+    marker: PhantomData<&'a (K, V)>,
+}
+
+// части выдают только общие ссылки на элементы
+unsafe impl<K: Sync, V: Sync> Send for ParIter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Send for ParIterProducer<'_, K, V> {}
+
+impl<'a, K: Sync, V: Sync> ParallelIterator for ParIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        let producer = ParIterProducer {
+            inner: self.inner,
+            marker: PhantomData,
+        };
+
+        bridge_unindexed(producer, consumer)
+    }
+}
+
+impl<'a, K: Sync, V: Sync> UnindexedProducer for ParIterProducer<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn split(self) -> (Self, Option<Self>) {
+        let (left, right) = self.inner.split();
+        let wrap = |inner| ParIterProducer {
+            inner,
+            marker: PhantomData,
+        };
+
+        (wrap(left), right.map(wrap))
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, folder: F) -> F {
+        folder.consume_iter(Iter {
+            inner: self.inner,
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<'a, K: Sync, V: Sync, S> IntoParallelIterator for &'a HashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type Iter = ParIter<'a, K, V>;
+
+    fn into_par_iter(self) -> ParIter<'a, K, V> {
+        ParIter {
+            inner: self.raw_iter(),
+            marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::HashMap;
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+    #[test]
+    fn par_sum() {
+        let hashmap: HashMap<usize, f32> = (0..1_000_000).map(|i| (i, (i % 1000) as f32)).collect();
+
+        // целые значения складываются в `f64` точно, поэтому порядок сложения не важен
+        let sequential: f64 = hashmap.values().map(|&value| value as f64).sum();
+        let parallel: f64 = hashmap
+            .into_par_iter()
+            .map(|(_, &value)| value as f64)
+            .sum();
+
+        assert_eq!(parallel, sequential);
+        assert_eq!(hashmap.into_par_iter().count(), 1_000_000);
+    }
+}
@@ -0,0 +1,312 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash};
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+
+use crate::{buckets_for, make_hash, max_load, DefaultHashBuilder, DEFAULT_MAX_LOAD_FACTOR};
+
+struct Bucket<K, V> {
+    hash: usize,
+    key: K,
+    value: V,
+}
+
+// насколько слот `index` удален от слота, с которого начинается пробирование для `hash`
+fn distance(hash: usize, index: usize, capacity: usize) -> usize {
+    (index + capacity - hash % capacity) % capacity
+}
+
+/// вариант таблицы с пробированием Робин Гуда: при вставке элемент, ушедший от своего слота
+/// дальше, вытесняет тот, что ушел ближе, поэтому длины цепочек выравниваются, а поиск
+/// прекращается, как только встречает элемент ближе к своему слоту, чем искомый
+pub struct RobinHoodMap<K, V, S = DefaultHashBuilder> {
+    buckets: Vec<Option<Bucket<K, V>>>,
+    items: usize,
+    hash_builder: S,
+}
+
+#[cfg(feature = "std")]
+impl<K, V> RobinHoodMap<K, V, RandomState> {
+    pub fn new() -> RobinHoodMap<K, V, RandomState> {
+        Self::with_hasher(RandomState::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> RobinHoodMap<K, V, RandomState> {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V> Default for RobinHoodMap<K, V, RandomState> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, S> RobinHoodMap<K, V, S> {
+    pub fn with_hasher(hash_builder: S) -> RobinHoodMap<K, V, S> {
+        RobinHoodMap {
+            buckets: Vec::new(),
+            items: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> RobinHoodMap<K, V, S> {
+        RobinHoodMap {
+            buckets: (0..buckets_for(capacity, DEFAULT_MAX_LOAD_FACTOR))
+                .map(|_| None)
+                .collect(),
+            items: 0,
+            hash_builder,
+        }
+    }
+
+    /// сколько элементов поместится в таблицу без перевыделения памяти
+    pub fn capacity(&self) -> usize {
+        max_load(self.buckets(), DEFAULT_MAX_LOAD_FACTOR)
+    }
+
+    pub fn buckets(&self) -> usize {
+        self.buckets.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items == 0
+    }
+
+    /// наибольшее расстояние от элемента до слота, с которого начинается его поиск
+    pub fn max_probe_length(&self) -> usize {
+        let capacity = self.buckets();
+
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, bucket)| Some(distance(bucket.as_ref()?.hash, idx, capacity)))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> RobinHoodMap<K, V, S> {
+    fn find<Q>(&self, hash: usize, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq,
+    {
+        let capacity = self.buckets();
+
+        if capacity == 0 {
+            return None;
+        }
+
+        let mut idx = hash % capacity;
+
+        for dist in 0..capacity {
+            let bucket = self.buckets[idx].as_ref()?;
+
+            // будь искомый ключ в таблице, он бы вытеснил этот элемент
+            if distance(bucket.hash, idx, capacity) < dist {
+                return None;
+            }
+
+            if bucket.hash == hash && bucket.key.borrow() == key {
+                return Some(idx);
+            }
+
+            idx = (idx + 1) % capacity;
+        }
+
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = make_hash(&self.hash_builder, key);
+        let idx = self.find(hash, key)?;
+        self.buckets[idx].as_ref().map(|bucket| &bucket.value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = make_hash(&self.hash_builder, key);
+        let idx = self.find(hash, key)?;
+        self.buckets[idx].as_mut().map(|bucket| &mut bucket.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = make_hash(&self.hash_builder, key);
+        self.find(hash, key).is_some()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = make_hash(&self.hash_builder, &key);
+
+        if let Some(idx) = self.find(hash, &key) {
+            let bucket = self.buckets[idx].as_mut().unwrap();
+            return Some(core::mem::replace(&mut bucket.value, value));
+        }
+
+        self.reserve(1);
+        self.insert_inner(Bucket { hash, key, value });
+        None
+    }
+
+    // вставляет заведомо отсутствующий ключ, вытесняя элементы, которые ближе к своему слоту
+    fn insert_inner(&mut self, mut bucket: Bucket<K, V>) {
+        let capacity = self.buckets();
+        let mut idx = bucket.hash % capacity;
+        let mut dist = 0;
+
+        loop {
+            match &mut self.buckets[idx] {
+                None => break,
+                Some(existing) => {
+                    let existing_dist = distance(existing.hash, idx, capacity);
+
+                    if existing_dist < dist {
+                        core::mem::swap(existing, &mut bucket);
+                        dist = existing_dist;
+                    }
+                }
+            }
+
+            idx = (idx + 1) % capacity;
+            dist += 1;
+        }
+
+        self.buckets[idx] = Some(bucket);
+        self.items += 1;
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = make_hash(&self.hash_builder, key);
+        let mut hole = self.find(hash, key)?;
+        let removed = self.buckets[hole].take()?;
+        let capacity = self.buckets();
+
+        // сдвигаем назад следующие элементы, пока они не на своих местах,
+        // поэтому надгробия не нужны
+        loop {
+            let next = (hole + 1) % capacity;
+
+            match &self.buckets[next] {
+                Some(bucket) if distance(bucket.hash, next, capacity) > 0 => {
+                    self.buckets[hole] = self.buckets[next].take();
+                    hole = next;
+                }
+                _ => break,
+            }
+        }
+
+        self.items -= 1;
+        Some(removed.value)
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self
+            .items
+            .checked_add(additional)
+            .expect("capacity overflow");
+
+        if needed > self.capacity() {
+            let buckets = (0..buckets_for(needed, DEFAULT_MAX_LOAD_FACTOR))
+                .map(|_| None)
+                .collect();
+            let old_buckets = core::mem::replace(&mut self.buckets, buckets);
+            self.items = 0;
+
+            for bucket in old_buckets.into_iter().flatten() {
+                self.insert_inner(bucket);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RobinHoodMap;
+    use crate::tests::keys_in_bucket;
+
+    #[test]
+    fn insert_get_remove() {
+        let mut map: RobinHoodMap<usize, String> = RobinHoodMap::new();
+
+        for key in 0..1000 {
+            assert_eq!(map.insert(key, key.to_string()), None);
+        }
+
+        assert_eq!(map.len(), 1000);
+        assert_eq!(map.insert(7, "seven".to_string()), Some("7".to_string()));
+
+        for key in (0..1000).step_by(2) {
+            assert!(map.remove(&key).is_some());
+        }
+
+        for key in 0..1000 {
+            assert_eq!(map.contains_key(&key), key % 2 == 1);
+        }
+
+        *map.get_mut(&7).unwrap() += "!";
+        assert_eq!(map.get(&7).map(String::as_str), Some("seven!"));
+        assert_eq!(map.len(), 500);
+    }
+
+    #[test]
+    fn bounded_probe_length() {
+        // 16 соседних слотов по 3 ключа, вставка от последнего слота к первому:
+        // при линейном пробировании ключи первого слота ушли бы в конец общей цепочки,
+        // на 47 слотов от него
+        let mut map: RobinHoodMap<usize, usize> = RobinHoodMap::with_capacity(56);
+        let keys: Vec<Vec<usize>> = (0..16)
+            .map(|bucket| keys_in_bucket(&map.hash_builder, map.buckets(), bucket, 3))
+            .collect();
+
+        for bucket in keys.iter().rev() {
+            for &key in bucket {
+                map.insert(key, key);
+            }
+        }
+
+        assert_eq!(map.buckets(), 64);
+        assert!(map.max_probe_length() <= 32);
+
+        for &key in keys.iter().flatten() {
+            assert_eq!(map.get(&key), Some(&key));
+        }
+
+        for &key in &keys[0] {
+            assert_eq!(map.remove(&key), Some(key));
+        }
+
+        assert!(map.max_probe_length() <= 30);
+        assert_eq!(map.len(), 45);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn reserve_overflow() {
+        let mut map: RobinHoodMap<usize, usize> = RobinHoodMap::new();
+        map.insert(1, 1);
+        map.reserve(usize::MAX);
+    }
+}
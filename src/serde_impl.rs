@@ -0,0 +1,91 @@
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::HashMap;
+
+// таблица сериализуется как обычный словарь в порядке слотов
+impl<K, V, S> Serialize for HashMap<K, V, S>
+where
+    K: Serialize,
+    V: Serialize,
+{
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+
+        for (key, value) in self {
+            map.serialize_entry(key, value)?;
+        }
+
+        map.end()
+    }
+}
+
+struct MapVisitor<K, V, S> {
+    marker: PhantomData<HashMap<K, V, S>>,
+}
+
+impl<'de, K, V, S> Visitor<'de> for MapVisitor<K, V, S>
+where
+    K: Deserialize<'de> + Hash + Eq,
+    V: Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    type Value = HashMap<K, V, S>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        // размер из входных данных не проверен, поэтому не доверяем ему слепо
+        let capacity = access.size_hint().unwrap_or(0).min(4096);
+        let mut map = HashMap::with_capacity_and_hasher(capacity, S::default());
+
+        while let Some((key, value)) = access.next_entry()? {
+            map.insert(key, value);
+        }
+
+        Ok(map)
+    }
+}
+
+impl<'de, K, V, S> Deserialize<'de> for HashMap<K, V, S>
+where
+    K: Deserialize<'de> + Hash + Eq,
+    V: Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(MapVisitor {
+            marker: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::HashMap;
+
+    #[test]
+    fn round_trip() {
+        let hashmap: HashMap<String, usize> = (0..100).map(|i| (i.to_string(), i)).collect();
+
+        let json = serde_json::to_string(&hashmap).unwrap();
+        let restored: HashMap<String, usize> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored, hashmap);
+    }
+
+    #[test]
+    fn empty() {
+        let hashmap: HashMap<String, usize> = HashMap::new();
+        assert_eq!(serde_json::to_string(&hashmap).unwrap(), "{}");
+
+        let restored: HashMap<String, usize> = serde_json::from_str("{}").unwrap();
+        assert!(restored.is_empty());
+    }
+}
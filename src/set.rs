@@ -0,0 +1,159 @@
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash};
+use core::iter::FusedIterator;
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+
+use crate::{DefaultHashBuilder, HashMap, Keys};
+
+/// множество поверх `HashMap<K, ()>`, значения нулевого размера памяти не занимают
+pub struct HashSet<K, S = DefaultHashBuilder> {
+    map: HashMap<K, (), S>,
+}
+
+#[cfg(feature = "std")]
+impl<K> HashSet<K, RandomState> {
+    pub fn new() -> HashSet<K, RandomState> {
+        HashSet {
+            map: HashMap::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> HashSet<K, RandomState> {
+        HashSet {
+            map: HashMap::with_capacity(capacity),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K> Default for HashSet<K, RandomState> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, S> HashSet<K, S> {
+    pub fn with_hasher(hash_builder: S) -> HashSet<K, S> {
+        HashSet {
+            map: HashMap::with_hasher(hash_builder),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    pub fn iter(&self) -> Iter<'_, K> {
+        Iter {
+            inner: self.map.keys(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, S: BuildHasher> HashSet<K, S> {
+    /// возвращает `false`, если такой элемент уже был в множестве
+    pub fn insert(&mut self, key: K) -> bool {
+        self.map.insert(key, ()).is_none()
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// возвращает `true`, если элемент был в множестве
+    pub fn remove<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.map.remove(key).is_some()
+    }
+}
+
+/// итератор по элементам множества
+pub struct Iter<'a, K> {
+    inner: Keys<'a, K, ()>,
+}
+
+impl<K> Clone for Iter<'_, K> {
+    fn clone(&self) -> Self {
+        Iter {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K> Iterator for Iter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K> ExactSizeIterator for Iter<'_, K> {}
+
+impl<K> FusedIterator for Iter<'_, K> {}
+
+impl<'a, K, S> IntoIterator for &'a HashSet<K, S> {
+    type Item = &'a K;
+    type IntoIter = Iter<'a, K>;
+
+    fn into_iter(self) -> Iter<'a, K> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HashSet;
+
+    #[test]
+    fn insert_duplicate() {
+        let mut set: HashSet<usize> = HashSet::new();
+
+        assert!(set.insert(1));
+        assert!(set.insert(2));
+        assert!(!set.insert(1));
+        assert_eq!(set.len(), 2);
+
+        assert!(set.contains(&1));
+        assert!(set.remove(&1));
+        assert!(!set.remove(&1));
+        assert!(!set.contains(&1));
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn iter() {
+        let mut set: HashSet<String> = HashSet::new();
+
+        for word in ["b", "a", "c", "a"] {
+            set.insert(word.to_string());
+        }
+
+        let mut words: Vec<&str> = set.iter().map(String::as_str).collect();
+        words.sort();
+
+        assert_eq!(words, ["a", "b", "c"]);
+        assert_eq!((&set).into_iter().count(), 3);
+        assert_eq!(set.iter().len(), 3);
+    }
+}